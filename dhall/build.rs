@@ -70,6 +70,17 @@ fn dhall_files_in_dir<'a>(
         })
 }
 
+/// Import tests that mention a remote url fetch it over the network. They only run if the
+/// `DHALL_TEST_NETWORK` environment variable is set.
+fn needs_network(file: &str) -> bool {
+    env::var_os("DHALL_TEST_NETWORK").is_none()
+        && ["../dhall-lang/tests/", "tests/"].iter().any(|dir| {
+            read_to_string(Path::new(dir).join(file))
+                .map(|s| s.contains("http://") || s.contains("https://"))
+                .unwrap_or(false)
+        })
+}
+
 struct TestFeature {
     /// Name of the module, used in the output of `cargo test`
     module_name: &'static str,
@@ -138,6 +149,7 @@ fn generate_tests() -> std::io::Result<()> {
     // Tries to detect when the submodule gets updated.
     // To force regeneration of the test list, just `touch dhall-lang/.git`
     println!("cargo:rerun-if-changed=../dhall-lang/.git");
+    println!("cargo:rerun-if-env-changed=DHALL_TEST_NETWORK");
     println!(
         "cargo:rerun-if-changed=../.git/modules/dhall-lang/refs/heads/master"
     );
@@ -239,16 +251,7 @@ fn generate_tests() -> std::io::Result<()> {
             directory: "import/success/",
             variant: "ImportSuccess",
             path_filter: Box::new(|path: &str| {
-                false
-                    || path == "alternativeEnvNatural"
-                    || path == "alternativeEnvSimple"
-                    || path == "alternativeHashMismatch"
-                    || path == "asLocation"
-                    || path == "asText"
-                    || path == "customHeaders"
-                    || path == "hashFromCache"
-                    || path == "headerForwarding"
-                    || path == "noHeaderForwarding"
+                needs_network(&format!("import/success/{}A.dhall", path))
             }),
             input_type: FileType::Text,
             output_type: Some(FileType::Text),
@@ -258,13 +261,7 @@ fn generate_tests() -> std::io::Result<()> {
             directory: "import/failure/",
             variant: "ImportFailure",
             path_filter: Box::new(|path: &str| {
-                false
-                    || path == "alternativeEnv"
-                    || path == "alternativeEnvMissing"
-                    || path == "hashMismatch"
-                    || path == "missing"
-                    || path == "referentiallyInsane"
-                    || path == "customHeadersUsingBoundVariable"
+                needs_network(&format!("import/failure/{}.dhall", path))
             }),
             input_type: FileType::Text,
             output_type: Some(FileType::UI),
@@ -278,13 +275,8 @@ fn generate_tests() -> std::io::Result<()> {
                 path == "simple/integerToDouble"
                     // Too slow
                     || path == "remoteSystems"
-                    // TODO: projection by expression
-                    || path == "unit/RecordProjectionByTypeEmpty"
-                    || path == "unit/RecordProjectionByTypeNonEmpty"
-                    || path == "unit/RecordProjectionByTypeNormalizeProjection"
-                    || path == "unit/RecordProjectionByTypeWithinFieldSelection"
+                    // TODO: Further record simplifications
                     || path == "unit/RecursiveRecordMergeWithinFieldSelection1"
-                    || path == "unit/NestedRecordProjectionByType"
                     // TODO: fix Double/show
                     || path == "prelude/JSON/number/1"
                     // TODO: doesn't typecheck
@@ -295,13 +287,9 @@ fn generate_tests() -> std::io::Result<()> {
                     || path == "simplifications/rightBiasedMergeWithinRecordProjectionWithinFieldSelection1"
                     || path == "simplifications/rightBiasedMergeWithinRecursiveRecordMergeWithinFieldselection"
                     || path == "simplifications/issue661"
-                    || path == "unit/RecordProjectionWithinFieldSelection"
                     || path == "unit/RecursiveRecordMergeWithinFieldSelection0"
                     || path == "unit/RecursiveRecordMergeWithinFieldSelection2"
                     || path == "unit/RecursiveRecordMergeWithinFieldSelection3"
-                    // TODO: record completion
-                    || path == "simple/completion"
-                    || path == "unit/Completion"
             }),
             input_type: FileType::Text,
             output_type: Some(FileType::Text),
@@ -413,6 +401,111 @@ fn convert_abnf_to_pest() -> std::io::Result<()> {
     }}"
     )?;
 
+    // Add the `with` keyword: `e with a.b = v` binds tighter than operators and looser than
+    // function application. The value cannot itself contain `with` at the top level, so that
+    // `e with a = x with b = y` chains.
+    rules.remove("keyword");
+    writeln!(
+        &mut file,
+        "keyword = _{{
+            if_ | then | else_ | let_ | in_ | using | missing | as_
+            | Infinity | NaN | merge | Some_ | toMap | assert | forall | with
+            | showConstructor
+    }}"
+    )?;
+    writeln!(
+        &mut file,
+        r#"
+        with = _{{ "with" }}
+        with_expression = {{ application_expression ~ (whsp1 ~ with ~ whsp1 ~ with_clause)* }}
+        with_clause = {{ any_label_or_some ~ (whsp ~ "." ~ whsp ~ any_label_or_some)* ~ whsp ~ "=" ~ whsp ~ with_value }}
+        with_value = {{ application_expression ~ (whsp ~ operator ~ whsp ~ application_expression)* }}
+    "#
+    )?;
+
+    // Add the `showConstructor` keyword, which parses like `toMap`.
+    rules.remove("first_application_expression");
+    writeln!(
+        &mut file,
+        r#"
+        showConstructor = {{ "showConstructor" }}
+        first_application_expression = {{
+              merge ~ whsp1 ~ import_expression ~ whsp1 ~ import_expression
+            | Some_ ~ whsp1 ~ import_expression
+            | toMap ~ whsp1 ~ import_expression
+            | showConstructor ~ whsp1 ~ import_expression
+            | import_expression
+        }}
+    "#
+    )?;
+
+    // Add the `Text/replace`, `Bytes` and temporal builtins. Longer names come first so that
+    // e.g. `TimeZone` is not parsed as `Time`.
+    rules.remove("builtin");
+    writeln!(
+        &mut file,
+        r#"
+        Text_replace = _{{ "Text/replace" }}
+        Bytes_show = _{{ "Bytes/show" }}
+        Bytes = _{{ "Bytes" }}
+        Date_show = _{{ "Date/show" }}
+        Time_show = _{{ "Time/show" }}
+        TimeZone_show = _{{ "TimeZone/show" }}
+        Date = _{{ "Date" }}
+        Time = _{{ "Time" }}
+        TimeZone = _{{ "TimeZone" }}
+        builtin = {{
+              Natural_fold | Natural_build | Natural_isZero | Natural_even
+            | Natural_odd | Natural_toInteger | Natural_show | Integer_toDouble
+            | Integer_show | Integer_negate | Integer_clamp | Natural_subtract
+            | Double_show | List_build | List_fold | List_length | List_head
+            | List_last | List_indexed | List_reverse | Optional_fold
+            | Optional_build | Text_show | Text_replace | Bytes_show
+            | Date_show | TimeZone_show | Time_show | Bool | True | False
+            | Optional | None_ | Natural | Integer | Double | Text | Bytes
+            | List | Date | TimeZone | Time | Type | Kind | Sort
+        }}
+    "#
+    )?;
+
+    // Temporal literals: `2020-01-01`, `12:00:00`, `+01:00` and their combinations. These must
+    // be tried before numeric literals, and so must `0x"..."` bytes literals.
+    rules.remove("primitive_expression");
+    writeln!(
+        &mut file,
+        r#"
+        full_date = {{ DIGIT{{4}} ~ "-" ~ DIGIT{{2}} ~ "-" ~ DIGIT{{2}} }}
+        partial_time = {{
+            DIGIT{{2}} ~ ":" ~ DIGIT{{2}} ~ ":" ~ DIGIT{{2}} ~ ("." ~ DIGIT+)?
+        }}
+        time_numoffset = {{ ("+" | "-") ~ DIGIT{{2}} ~ ":" ~ DIGIT{{2}} }}
+        time_zone_utc = {{ ^"Z" }}
+        time_offset = _{{ time_zone_utc | time_numoffset }}
+        temporal_literal = {{
+              full_date ~ ^"T" ~ partial_time ~ time_offset
+            | full_date ~ ^"T" ~ partial_time
+            | partial_time ~ time_offset
+            | full_date
+            | partial_time
+            | time_numoffset
+        }}
+        bytes_literal = {{ "0x" ~ "\"" ~ (HEXDIG ~ HEXDIG)* ~ "\"" }}
+        primitive_expression = {{
+              temporal_literal
+            | bytes_literal
+            | double_literal
+            | natural_literal
+            | integer_literal
+            | text_literal
+            | "{{" ~ whsp ~ ("," ~ whsp)? ~ record_type_or_literal ~ whsp ~ "}}"
+            | "<" ~ whsp ~ ("|" ~ whsp)? ~ union_type ~ whsp ~ ">"
+            | non_empty_list_literal
+            | identifier
+            | "(" ~ complete_expression ~ ")"
+        }}
+    "#
+    )?;
+
     // Setup grammar for precedence climbing
     rules.remove("operator_expression");
     writeln!(&mut file, r##"
@@ -441,7 +534,7 @@ fn convert_abnf_to_pest() -> std::io::Result<()> {
             bool_or |
             import_alt
         }}
-        operator_expression = {{ application_expression ~ (whsp ~ operator ~ whsp ~ application_expression)* }}
+        operator_expression = {{ with_expression ~ (whsp ~ operator ~ whsp ~ with_expression)* }}
     "##)?;
 
     writeln!(
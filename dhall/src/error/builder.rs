@@ -30,15 +30,35 @@ struct FreeAnnotation {
 }
 
 impl SpannedAnnotation {
-    fn into_annotation(self) -> SourceAnnotation {
+    fn into_annotation(self, range: (usize, usize)) -> SourceAnnotation {
         SourceAnnotation {
             label: self.message,
             annotation_type: self.annotation_type,
-            range: self.span.as_char_range(),
+            range,
         }
     }
 }
 
+/// Render the annotations pointing into the same source as a single slice, made of the lines
+/// they cover.
+fn make_slice(annotations: Vec<SpannedAnnotation>) -> Slice {
+    let spans: Vec<_> = annotations.iter().map(|annot| &annot.span).collect();
+    let (source, line_start, ranges) = ParsedSpan::excerpt(&spans);
+    let origin = Some(annotations[0].span.origin().to_string());
+    let annotations = annotations
+        .into_iter()
+        .zip(ranges)
+        .map(|(annot, range)| annot.into_annotation(range))
+        .collect();
+    Slice {
+        source,
+        line_start,
+        origin,
+        fold: true,
+        annotations,
+    }
+}
+
 impl FreeAnnotation {
     fn into_annotation(self) -> Annotation {
         Annotation {
@@ -115,7 +135,6 @@ impl ErrorBuilder {
         self.footer_annot(message, AnnotationType::Note)
     }
 
-    // TODO: handle multiple files
     pub fn format(&mut self) -> String {
         if self.consumed {
             panic!("tried to format the same ErrorBuilder twice")
@@ -124,23 +143,18 @@ impl ErrorBuilder {
         self.consumed = true;
         drop(self); // Get rid of the self reference so we don't use it by mistake.
 
-        let slices = if this.annotations.is_empty() {
-            Vec::new()
-        } else {
-            let input = this.annotations[0].span.to_input();
-            let annotations = this
-                .annotations
-                .into_iter()
-                .map(|annot| annot.into_annotation())
-                .collect();
-            vec![Slice {
-                source: input,
-                line_start: 1, // TODO
-                origin: Some("<current file>".to_string()),
-                fold: true,
-                annotations,
-            }]
-        };
+        // Group the annotations by source file, in order of first appearance.
+        let mut groups: Vec<Vec<SpannedAnnotation>> = Vec::new();
+        for annot in this.annotations {
+            match groups
+                .iter_mut()
+                .find(|group| group[0].span.same_source(&annot.span))
+            {
+                Some(group) => group.push(annot),
+                None => groups.push(vec![annot]),
+            }
+        }
+        let slices = groups.into_iter().map(make_slice).collect();
         let footer = this
             .footer
             .into_iter()
@@ -1,7 +1,7 @@
 use std::io::Error as IOError;
 
-use crate::semantics::resolve::ImportStack;
-use crate::syntax::{Import, ParseError};
+use crate::semantics::resolve::{import_origin, ImportStack};
+use crate::syntax::{BinOp, Hash, Import, Label, ParseError, Span, V};
 use crate::NormalizedExpr;
 
 mod builder;
@@ -25,6 +25,16 @@ pub enum ImportError {
     Recursive(Import<NormalizedExpr>, Box<Error>),
     UnexpectedImport(Import<NormalizedExpr>),
     ImportCycle(ImportStack, Import<NormalizedExpr>),
+    SanityCheck(Import<NormalizedExpr>),
+    MissingEnvVar(Import<NormalizedExpr>),
+    NoHomeDir,
+    Missing,
+    CorsDisallowed(String, Import<NormalizedExpr>),
+    MissingImports(Vec<ImportError>),
+    /// The import and the actual hash of its contents.
+    HashMismatch(Import<NormalizedExpr>, Hash),
+    InvalidUtf8(std::str::Utf8Error),
+    Forbidden(Import<NormalizedExpr>),
 }
 
 #[derive(Debug)]
@@ -41,49 +51,948 @@ pub enum EncodeError {
 /// A structured type error
 #[derive(Debug)]
 pub struct TypeError {
-    message: TypeMessage,
+    message: Box<TypeMessage>,
+    /// Where the imports leading to the file where the error occurred come from, outermost
+    /// first. Computed when the error is raised, so that formatting doesn't depend on the
+    /// current directory.
+    import_stack: Vec<String>,
 }
 
-/// The specific type error
+/// The specific type error.
+///
+/// Each variant carries the spans of the offending subexpressions, along with the relevant types
+/// in normal form, so that the error can be rendered as an annotated snippet of the source.
 #[derive(Debug)]
-pub(crate) enum TypeMessage {
+#[non_exhaustive]
+#[allow(clippy::large_enum_variant)] // Always boxed in `TypeError`
+pub enum TypeMessage {
+    /// `Sort` has no type.
     Sort,
-    Custom(String),
+    UnboundVariable {
+        span: Span,
+        var: V,
+    },
+    InvalidInputType {
+        span: Span,
+        ty: NormalizedExpr,
+    },
+    InvalidOutputType {
+        span: Span,
+        ty: NormalizedExpr,
+    },
+    NotAFunction {
+        span: Span,
+        ty: NormalizedExpr,
+    },
+    /// A function was applied to an argument of the wrong type.
+    TypeMismatch {
+        function: Span,
+        argument: Span,
+        expected: NormalizedExpr,
+        actual: NormalizedExpr,
+    },
+    AnnotMismatch {
+        span: Span,
+        expected: NormalizedExpr,
+        actual: NormalizedExpr,
+    },
+    InvalidTextInterpolation {
+        span: Span,
+        ty: NormalizedExpr,
+    },
+    InvalidListType {
+        span: Span,
+        ty: NormalizedExpr,
+    },
+    InvalidListElement {
+        span: Span,
+        expected: NormalizedExpr,
+        actual: NormalizedExpr,
+    },
+    InvalidOptionalType {
+        span: Span,
+        ty: NormalizedExpr,
+    },
+    InvalidFieldType {
+        span: Span,
+    },
+    RecordTypeDuplicateField {
+        span: Span,
+        label: Label,
+    },
+    UnionTypeDuplicateField {
+        span: Span,
+        label: Label,
+    },
+    MissingRecordField {
+        span: Span,
+        label: Label,
+        record_type: NormalizedExpr,
+    },
+    MissingUnionField {
+        span: Span,
+        label: Label,
+        union_type: NormalizedExpr,
+    },
+    NotARecord {
+        span: Span,
+        label: Label,
+        ty: NormalizedExpr,
+    },
+    AssertMismatch {
+        span: Span,
+        lhs: NormalizedExpr,
+        rhs: NormalizedExpr,
+    },
+    AssertMustTakeEquivalence {
+        span: Span,
+        ty: NormalizedExpr,
+    },
+    InvalidPredicate {
+        span: Span,
+        ty: NormalizedExpr,
+    },
+    IfBranchMustBeTerm {
+        span: Span,
+        ty: NormalizedExpr,
+    },
+    IfBranchMismatch {
+        then_span: Span,
+        else_span: Span,
+        then_type: NormalizedExpr,
+        else_type: NormalizedExpr,
+    },
+    MustCombineRecord {
+        span: Span,
+        ty: NormalizedExpr,
+    },
+    WithMustBeRecord {
+        span: Span,
+        ty: NormalizedExpr,
+    },
+    RecordTypeMergeRequiresRecordType {
+        span: Span,
+        ty: NormalizedExpr,
+    },
+    BinOpTypeMismatch {
+        span: Span,
+        op: BinOp,
+        ty: NormalizedExpr,
+    },
+    EquivalenceTypeMismatch {
+        lhs_span: Span,
+        rhs_span: Span,
+        lhs_type: NormalizedExpr,
+        rhs_type: NormalizedExpr,
+    },
+    EquivalenceArgumentsMustBeTerms {
+        span: Span,
+        ty: NormalizedExpr,
+    },
+    Merge1ArgMustBeRecord {
+        span: Span,
+        ty: NormalizedExpr,
+    },
+    Merge2ArgMustBeUnionOrOptional {
+        span: Span,
+        ty: NormalizedExpr,
+    },
+    MergeHandlerNotAFunction {
+        span: Span,
+        handlers: Span,
+        variants: Span,
+        label: Label,
+        handler_type: NormalizedExpr,
+        variant_type: NormalizedExpr,
+    },
+    MergeHandlerInputMismatch {
+        span: Span,
+        handlers: Span,
+        variants: Span,
+        label: Label,
+        expected: NormalizedExpr,
+        actual: NormalizedExpr,
+    },
+    MergeReturnTypeIsDependent {
+        span: Span,
+        label: Label,
+    },
+    MergeHandlerMissingVariant {
+        span: Span,
+        label: Label,
+    },
+    MergeHandlerTypeMismatch {
+        span: Span,
+        label: Label,
+        expected: NormalizedExpr,
+        actual: NormalizedExpr,
+    },
+    MergeVariantMissingHandler {
+        span: Span,
+        label: Label,
+    },
+    MergeAnnotMismatch {
+        span: Span,
+        expected: NormalizedExpr,
+        actual: NormalizedExpr,
+    },
+    MergeEmptyNeedsAnnotation {
+        span: Span,
+    },
+    ShowConstructorNotOnUnion {
+        span: Span,
+        ty: NormalizedExpr,
+    },
+    MustMapARecord {
+        span: Span,
+        ty: NormalizedExpr,
+    },
+    MissingToMapType {
+        span: Span,
+    },
+    InvalidToMapType {
+        span: Span,
+        ty: NormalizedExpr,
+    },
+    InvalidToMapRecordKind {
+        span: Span,
+        ty: NormalizedExpr,
+    },
+    HeterogenousRecordToMap {
+        span: Span,
+        label: Label,
+        expected: NormalizedExpr,
+        actual: NormalizedExpr,
+    },
+    MapTypeMismatch {
+        span: Span,
+        expected: NormalizedExpr,
+        actual: NormalizedExpr,
+    },
+    ProjectionMustBeRecord {
+        span: Span,
+        ty: NormalizedExpr,
+    },
+    ProjectionMissingEntry {
+        span: Span,
+        label: Label,
+        record_type: NormalizedExpr,
+    },
+    ProjectionDuplicateField {
+        span: Span,
+        label: Label,
+    },
+    ProjectionByExprTakesRecordType {
+        span: Span,
+        ty: NormalizedExpr,
+    },
+    ProjectionByExprMissingFields {
+        record: Span,
+        selection: Span,
+        record_type: NormalizedExpr,
+        missing: Vec<Label>,
+    },
+    ProjectionByExprTypeMismatch {
+        span: Span,
+        label: Label,
+        expected: NormalizedExpr,
+        actual: NormalizedExpr,
+    },
 }
 
 impl TypeError {
     pub(crate) fn new(message: TypeMessage) -> Self {
-        TypeError { message }
+        TypeError {
+            message: Box::new(message),
+            import_stack: Vec::new(),
+        }
+    }
+
+    pub(crate) fn with_import_stack(self, import_stack: &ImportStack) -> Self {
+        TypeError {
+            import_stack: import_stack.iter().map(import_origin).collect(),
+            ..self
+        }
+    }
+
+    /// The kind of type error, for programmatic inspection.
+    pub fn message(&self) -> &TypeMessage {
+        &self.message
+    }
+}
+
+/// An error pointing at a single location.
+fn span_error(
+    title: String,
+    span: &Span,
+    label: impl ToString,
+) -> ErrorBuilder {
+    let mut builder = ErrorBuilder::new(title);
+    builder.span_err(span.clone(), label);
+    builder
+}
+
+fn mismatch_note(expected: &NormalizedExpr, actual: &NormalizedExpr) -> String {
+    format!("expected type `{}`\n   found type `{}`", expected, actual)
+}
+
+fn quote_labels(labels: &[Label]) -> String {
+    labels
+        .iter()
+        .map(|l| format!("`{}`", l))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl TypeMessage {
+    /// Describe the error as an annotated snippet of the source.
+    fn to_builder(&self) -> ErrorBuilder {
+        use TypeMessage::*;
+        match self {
+            Sort => ErrorBuilder::new("`Sort` has no type"),
+            UnboundVariable { span, var } => span_error(
+                format!("unbound variable `{}`", var),
+                span,
+                "not found in this scope",
+            ),
+            InvalidInputType { span, ty } => {
+                let mut builder =
+                    ErrorBuilder::new(format!("Invalid input type: `{}`", ty));
+                builder
+                    .span_err(span.clone(), format!("this has type: `{}`", ty))
+                    .help(
+                        "The input type of a function must have type `Type`, \
+                         `Kind` or `Sort`",
+                    );
+                builder
+            }
+            InvalidOutputType { span, ty } => {
+                let mut builder =
+                    ErrorBuilder::new(format!("Invalid output type: `{}`", ty));
+                builder
+                    .span_err(span.clone(), format!("this has type: `{}`", ty))
+                    .help(
+                        "The output type of a function must have type `Type`, \
+                         `Kind` or `Sort`",
+                    );
+                builder
+            }
+            NotAFunction { span, ty } => span_error(
+                format!("expected function, found `{}`", ty),
+                span,
+                "function application requires a function",
+            ),
+            TypeMismatch {
+                function,
+                argument,
+                expected,
+                actual,
+            } => {
+                let mut builder =
+                    ErrorBuilder::new("wrong type of function argument");
+                builder
+                    .span_err(
+                        function.clone(),
+                        format!(
+                            "this expects an argument of type: {}",
+                            expected
+                        ),
+                    )
+                    .span_err(
+                        argument.clone(),
+                        format!("but this has type: {}", actual),
+                    )
+                    .note(mismatch_note(expected, actual));
+                builder
+            }
+            AnnotMismatch {
+                span,
+                expected,
+                actual,
+            } => {
+                let mut builder = ErrorBuilder::new("type annotation mismatch");
+                builder
+                    .span_err(
+                        span.clone(),
+                        format!("this is annotated with type `{}`", expected),
+                    )
+                    .note(mismatch_note(expected, actual));
+                builder
+            }
+            InvalidTextInterpolation { span, ty } => span_error(
+                "only `Text` can be interpolated".to_string(),
+                span,
+                format!("this has type `{}`", ty),
+            ),
+            InvalidListType { span, ty } => span_error(
+                format!("invalid type for a list: `{}`", ty),
+                span,
+                "a list must have type `List T` with `T : Type`",
+            ),
+            InvalidListElement {
+                span,
+                expected,
+                actual,
+            } => {
+                let mut builder = ErrorBuilder::new(
+                    "list elements must all have the same type",
+                );
+                builder
+                    .span_err(
+                        span.clone(),
+                        format!("this has type `{}`", actual),
+                    )
+                    .note(mismatch_note(expected, actual));
+                builder
+            }
+            InvalidOptionalType { span, ty } => span_error(
+                format!("invalid type for an optional value: `{}`", ty),
+                span,
+                "the argument to `Some` must be a term",
+            ),
+            InvalidFieldType { span } => span_error(
+                "invalid field type".to_string(),
+                span,
+                "the fields of this type must all be types of the same kind",
+            ),
+            RecordTypeDuplicateField { span, label } => span_error(
+                format!("duplicate record field `{}`", label),
+                span,
+                format!("`{}` appears more than once", label),
+            ),
+            UnionTypeDuplicateField { span, label } => span_error(
+                format!("duplicate union alternative `{}`", label),
+                span,
+                format!("`{}` appears more than once", label),
+            ),
+            MissingRecordField {
+                span,
+                label,
+                record_type,
+            } => span_error(
+                format!("missing record field `{}`", label),
+                span,
+                format!("this has type `{}`", record_type),
+            ),
+            MissingUnionField {
+                span,
+                label,
+                union_type,
+            } => span_error(
+                format!("missing union alternative `{}`", label),
+                span,
+                format!("this is the union type `{}`", union_type),
+            ),
+            NotARecord { span, label, ty } => span_error(
+                format!("cannot access field `{}` of a non-record", label),
+                span,
+                format!("this has type `{}`", ty),
+            ),
+            AssertMismatch { span, lhs, rhs } => {
+                let mut builder = ErrorBuilder::new("assertion failed");
+                builder
+                    .span_err(
+                        span.clone(),
+                        "the two sides of this equivalence differ",
+                    )
+                    .note(format!(
+                        "left side: `{}`\nright side: `{}`",
+                        lhs, rhs
+                    ));
+                builder
+            }
+            AssertMustTakeEquivalence { span, ty } => span_error(
+                "`assert` expects an equivalence".to_string(),
+                span,
+                format!("this asserts `{}`", ty),
+            ),
+            InvalidPredicate { span, ty } => span_error(
+                "invalid predicate for `if`".to_string(),
+                span,
+                format!("this has type `{}` instead of `Bool`", ty),
+            ),
+            IfBranchMustBeTerm { span, ty } => span_error(
+                "the branches of an `if` must be terms".to_string(),
+                span,
+                format!("this has type `{}`", ty),
+            ),
+            IfBranchMismatch {
+                then_span,
+                else_span,
+                then_type,
+                else_type,
+            } => {
+                let mut builder =
+                    ErrorBuilder::new("`if` branches have different types");
+                builder
+                    .span_err(
+                        then_span.clone(),
+                        format!("this has type `{}`", then_type),
+                    )
+                    .span_err(
+                        else_span.clone(),
+                        format!("but this has type `{}`", else_type),
+                    );
+                builder
+            }
+            MustCombineRecord { span, ty } => span_error(
+                "only records can be combined".to_string(),
+                span,
+                format!("this has type `{}`", ty),
+            ),
+            WithMustBeRecord { span, ty } => span_error(
+                "only records can be updated with `with`".to_string(),
+                span,
+                format!("this has type `{}`", ty),
+            ),
+            RecordTypeMergeRequiresRecordType { span, ty } => span_error(
+                "only record types can be combined".to_string(),
+                span,
+                format!("this is `{}`", ty),
+            ),
+            BinOpTypeMismatch { span, op, ty } => span_error(
+                format!("wrong type of argument to `{}`", op),
+                span,
+                format!("this has type `{}`", ty),
+            ),
+            EquivalenceTypeMismatch {
+                lhs_span,
+                rhs_span,
+                lhs_type,
+                rhs_type,
+            } => {
+                let mut builder = ErrorBuilder::new(
+                    "the two sides of an equivalence must have the same type",
+                );
+                builder
+                    .span_err(
+                        lhs_span.clone(),
+                        format!("this has type `{}`", lhs_type),
+                    )
+                    .span_err(
+                        rhs_span.clone(),
+                        format!("but this has type `{}`", rhs_type),
+                    );
+                builder
+            }
+            EquivalenceArgumentsMustBeTerms { span, ty } => span_error(
+                "only terms can be compared for equivalence".to_string(),
+                span,
+                format!("this has type `{}`", ty),
+            ),
+            Merge1ArgMustBeRecord { span, ty } => span_error(
+                "the first argument to `merge` must be a record".to_string(),
+                span,
+                format!("this has type `{}`", ty),
+            ),
+            Merge2ArgMustBeUnionOrOptional { span, ty } => span_error(
+                "the second argument to `merge` must be a union or an \
+                 `Optional`"
+                    .to_string(),
+                span,
+                format!("this has type `{}`", ty),
+            ),
+            MergeHandlerNotAFunction {
+                span,
+                handlers,
+                variants,
+                label,
+                handler_type,
+                variant_type,
+            } => {
+                let mut builder =
+                    ErrorBuilder::new("merge handler is not a function");
+                builder
+                    .span_err(span.clone(), "in this merge expression")
+                    .span_err(
+                        handlers.clone(),
+                        format!(
+                            "the handler for `{}` has type: `{}`",
+                            label, handler_type
+                        ),
+                    )
+                    .span_help(
+                        variants.clone(),
+                        format!(
+                            "the corresponding variant has type: `{}`",
+                            variant_type
+                        ),
+                    )
+                    .help(format!(
+                        "a handler for this variant must be a function that \
+                         takes an input of type: `{}`",
+                        variant_type
+                    ));
+                builder
+            }
+            MergeHandlerInputMismatch {
+                span,
+                handlers,
+                variants,
+                label,
+                expected,
+                actual,
+            } => {
+                let mut builder = ErrorBuilder::new("Wrong handler input type");
+                builder
+                    .span_err(span.clone(), "in this merge expression")
+                    .span_err(
+                        handlers.clone(),
+                        format!(
+                            "the handler for `{}` expects a value of type: \
+                             `{}`",
+                            label, expected
+                        ),
+                    )
+                    .span_err(
+                        variants.clone(),
+                        format!(
+                            "but the corresponding variant has type: `{}`",
+                            actual
+                        ),
+                    );
+                builder
+            }
+            MergeReturnTypeIsDependent { span, label } => span_error(
+                format!(
+                    "the output type of the handler for `{}` depends on its \
+                     input",
+                    label
+                ),
+                span,
+                "in this merge expression",
+            ),
+            MergeHandlerMissingVariant { span, label } => span_error(
+                format!(
+                    "there is a handler for `{}` but no such variant",
+                    label
+                ),
+                span,
+                "in this merge expression",
+            ),
+            MergeHandlerTypeMismatch {
+                span,
+                label,
+                expected,
+                actual,
+            } => {
+                let mut builder = ErrorBuilder::new(format!(
+                    "the handler for `{}` returns a different type than the \
+                     other handlers",
+                    label
+                ));
+                builder
+                    .span_err(span.clone(), "in this merge expression")
+                    .note(mismatch_note(expected, actual));
+                builder
+            }
+            MergeVariantMissingHandler { span, label } => span_error(
+                format!("missing handler for variant `{}`", label),
+                span,
+                "in this merge expression",
+            ),
+            MergeAnnotMismatch {
+                span,
+                expected,
+                actual,
+            } => {
+                let mut builder =
+                    ErrorBuilder::new("merge annotation mismatch");
+                builder
+                    .span_err(
+                        span.clone(),
+                        format!("this is annotated with type `{}`", expected),
+                    )
+                    .note(mismatch_note(expected, actual));
+                builder
+            }
+            MergeEmptyNeedsAnnotation { span } => span_error(
+                "an empty `merge` requires a type annotation".to_string(),
+                span,
+                "this has no handlers",
+            ),
+            ShowConstructorNotOnUnion { span, ty } => span_error(
+                "The argument to `showConstructor` must be a union or an \
+                 `Optional`"
+                    .to_string(),
+                span,
+                format!("this has type `{}`", ty),
+            ),
+            MustMapARecord { span, ty } => span_error(
+                "The argument to `toMap` must be a record".to_string(),
+                span,
+                format!("this has type `{}`", ty),
+            ),
+            MissingToMapType { span } => span_error(
+                "`toMap` applied to an empty record requires a type annotation"
+                    .to_string(),
+                span,
+                "this needs a type annotation",
+            ),
+            InvalidToMapType { span, ty } => span_error(
+                "The type of `toMap x` must be of the form `List { mapKey : \
+                 Text, mapValue : T }`"
+                    .to_string(),
+                span,
+                format!("this is annotated with type `{}`", ty),
+            ),
+            InvalidToMapRecordKind { span, ty } => span_error(
+                "`toMap` only accepts records of type `Type`".to_string(),
+                span,
+                format!("this has type `{}`", ty),
+            ),
+            HeterogenousRecordToMap {
+                span,
+                label,
+                expected,
+                actual,
+            } => {
+                let mut builder = ErrorBuilder::new(
+                    "Every field of the record must have the same type",
+                );
+                builder
+                    .span_err(
+                        span.clone(),
+                        format!("the field `{}` has a different type", label),
+                    )
+                    .note(mismatch_note(expected, actual));
+                builder
+            }
+            MapTypeMismatch {
+                span,
+                expected,
+                actual,
+            } => {
+                let mut builder = ErrorBuilder::new("Annotation mismatch");
+                builder
+                    .span_err(
+                        span.clone(),
+                        format!("this is annotated with type `{}`", expected),
+                    )
+                    .note(mismatch_note(expected, actual));
+                builder
+            }
+            ProjectionMustBeRecord { span, ty } => span_error(
+                "only records can be projected".to_string(),
+                span,
+                format!("this has type `{}`", ty),
+            ),
+            ProjectionMissingEntry {
+                span,
+                label,
+                record_type,
+            } => span_error(
+                format!("missing record field `{}`", label),
+                span,
+                format!("this has type `{}`", record_type),
+            ),
+            ProjectionDuplicateField { span, label } => span_error(
+                format!("duplicate field `{}` in projection", label),
+                span,
+                format!("`{}` is selected more than once", label),
+            ),
+            ProjectionByExprTakesRecordType { span, ty } => span_error(
+                "projection by type requires a record type".to_string(),
+                span,
+                format!("this is `{}`", ty),
+            ),
+            ProjectionByExprMissingFields {
+                record,
+                selection,
+                record_type,
+                missing,
+            } => {
+                let mut builder = ErrorBuilder::new(format!(
+                    "projection by type: missing fields {}",
+                    quote_labels(missing)
+                ));
+                builder
+                    .span_err(
+                        record.clone(),
+                        format!("this record has type `{}`", record_type),
+                    )
+                    .span_err(
+                        selection.clone(),
+                        format!("but this selects {}", quote_labels(missing)),
+                    );
+                builder
+            }
+            ProjectionByExprTypeMismatch {
+                span,
+                label,
+                expected,
+                actual,
+            } => {
+                let mut builder = ErrorBuilder::new(format!(
+                    "projection by type: wrong type for field `{}`",
+                    label
+                ));
+                builder
+                    .span_err(
+                        span.clone(),
+                        format!(
+                            "this expects `{}` to have type `{}`",
+                            label, expected
+                        ),
+                    )
+                    .note(mismatch_note(expected, actual));
+                builder
+            }
+        }
     }
 }
 
 impl std::fmt::Display for TypeError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        use TypeMessage::*;
-        let msg = match &self.message {
-            Sort => format!("Type error: Unhandled error: {:?}", self.message),
-            Custom(s) => format!("Type error: {}", s),
-        };
-        write!(f, "{}", msg)
+        let mut builder = self.message.to_builder();
+        let mut imports = self.import_stack.iter().rev();
+        if let Some(import) = imports.next() {
+            builder.note(format!("while checking the import `{}`", import));
+        }
+        for import in imports {
+            builder.note(format!("imported by `{}`", import));
+        }
+        write!(f, "Type error: {}", builder.format())
     }
 }
 
 impl std::error::Error for TypeError {}
 
+impl std::fmt::Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use ImportError::*;
+        match self {
+            Recursive(import, _) => write!(f, "Error in import `{}`", import),
+            UnexpectedImport(import) => write!(
+                f,
+                "Unexpected import `{}`: imports are not allowed here",
+                import
+            ),
+            ImportCycle(stack, import) => {
+                write!(f, "Import cycle detected: ")?;
+                for link in stack {
+                    write!(f, "`{}` -> ", link)?;
+                }
+                write!(f, "`{}`", import)
+            }
+            SanityCheck(import) => write!(
+                f,
+                "A remote file cannot import `{}`: remote imports may not \
+                 read local files or environment variables",
+                import
+            ),
+            MissingEnvVar(import) => write!(
+                f,
+                "Cannot resolve `{}`: the environment variable is not set",
+                import
+            ),
+            NoHomeDir => write!(f, "Could not find the home directory"),
+            Missing => write!(f, "Cannot resolve the `missing` import"),
+            CorsDisallowed(origin, import) => write!(
+                f,
+                "The server hosting `{}` does not allow imports from `{}` \
+                 (CORS)",
+                import, origin
+            ),
+            MissingImports(errs) => {
+                write!(f, "None of the alternatives could be imported:")?;
+                for err in errs {
+                    let err = err.to_string().replace("\n", "\n  ");
+                    write!(f, "\n- {}", err)?;
+                }
+                Ok(())
+            }
+            HashMismatch(import, actual) => {
+                write!(f, "Hash mismatch for import `{}`", import)?;
+                if let Some(expected) = &import.hash {
+                    write!(f, "\n  expected: {}", expected)?;
+                }
+                write!(f, "\n    actual: {}", actual)
+            }
+            InvalidUtf8(_) => write!(f, "Import is not valid UTF-8"),
+            Forbidden(import) => write!(
+                f,
+                "Import `{}` is forbidden by the import policy",
+                import
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImportError::Recursive(_, err) => Some(err.as_ref()),
+            ImportError::InvalidUtf8(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DecodeError::CBORError(_) => write!(f, "Failed to decode CBOR"),
+            DecodeError::WrongFormatError(msg) => write!(
+                f,
+                "Invalid binary encoding of a Dhall expression: {}",
+                msg
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::CBORError(err) => Some(err),
+            DecodeError::WrongFormatError(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            EncodeError::CBORError(_) => write!(f, "Failed to encode CBOR"),
+        }
+    }
+}
+
+impl std::error::Error for EncodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EncodeError::CBORError(err) => Some(err),
+        }
+    }
+}
+
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Error::IO(err) => write!(f, "{}", err),
             Error::Parse(err) => write!(f, "{}", err),
-            Error::Decode(err) => write!(f, "{:?}", err),
-            Error::Encode(err) => write!(f, "{:?}", err),
-            Error::Resolve(err) => write!(f, "{:?}", err),
+            Error::Decode(err) => write!(f, "{}", err),
+            Error::Encode(err) => write!(f, "{}", err),
+            Error::Resolve(err) => write!(f, "{}", err),
             Error::Typecheck(err) => write!(f, "{}", err),
         }
     }
 }
 
-impl std::error::Error for Error {}
+/// `Error` displays the error it wraps, so its source is that error's source.
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::IO(err) => err.source(),
+            Error::Parse(err) => err.source(),
+            Error::Decode(err) => err.source(),
+            Error::Encode(err) => err.source(),
+            Error::Resolve(err) => err.source(),
+            Error::Typecheck(err) => err.source(),
+        }
+    }
+}
 impl From<IOError> for Error {
     fn from(err: IOError) -> Error {
         Error::IO(err)
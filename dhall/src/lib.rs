@@ -21,7 +21,7 @@ use std::path::Path;
 use crate::error::{EncodeError, Error, ImportError, TypeError};
 use crate::semantics::parse;
 use crate::semantics::resolve;
-use crate::semantics::resolve::ImportRoot;
+use crate::semantics::resolve::{ImportResolver, ImportRoot};
 use crate::semantics::{typecheck, typecheck_with, TyExpr, Value, ValueKind};
 use crate::syntax::binary;
 use crate::syntax::{Builtin, Const, Expr};
@@ -76,6 +76,13 @@ impl Parsed {
     pub fn resolve(self) -> Result<Resolved, ImportError> {
         resolve::resolve(self)
     }
+    /// Resolve imports using the provided resolver.
+    pub fn resolve_with(
+        self,
+        resolver: &ImportResolver,
+    ) -> Result<Resolved, ImportError> {
+        resolve::resolve_with(self, resolver)
+    }
     pub fn skip_resolve(self) -> Result<Resolved, ImportError> {
         resolve::skip_resolve_expr(self)
     }
@@ -77,6 +77,10 @@ macro_rules! make_type {
     (Integer) => { rc(ExprKind::Builtin(Builtin::Integer)) };
     (Double) => { rc(ExprKind::Builtin(Builtin::Double)) };
     (Text) => { rc(ExprKind::Builtin(Builtin::Text)) };
+    (Bytes) => { rc(ExprKind::Builtin(Builtin::Bytes)) };
+    (Date) => { rc(ExprKind::Builtin(Builtin::Date)) };
+    (Time) => { rc(ExprKind::Builtin(Builtin::Time)) };
+    (TimeZone) => { rc(ExprKind::Builtin(Builtin::TimeZone)) };
     ($var:ident) => {
         rc(ExprKind::Var(V(stringify!($var).into(), 0)))
     };
@@ -128,7 +132,8 @@ macro_rules! make_type {
 pub(crate) fn type_of_builtin<E>(b: Builtin) -> Expr<E> {
     use Builtin::*;
     match b {
-        Bool | Natural | Integer | Double | Text => make_type!(Type),
+        Bool | Natural | Integer | Double | Text | Bytes | Date | Time
+        | TimeZone => make_type!(Type),
         List | Optional => make_type!(
             Type -> Type
         ),
@@ -161,6 +166,16 @@ pub(crate) fn type_of_builtin<E>(b: Builtin) -> Expr<E> {
 
         DoubleShow => make_type!(Double -> Text),
         TextShow => make_type!(Text -> Text),
+        TextReplace => make_type!(
+            forall (needle: Text) ->
+            forall (replacement: Text) ->
+            forall (haystack: Text) ->
+            Text
+        ),
+        BytesShow => make_type!(Bytes -> Text),
+        DateShow => make_type!(Date -> Text),
+        TimeShow => make_type!(Time -> Text),
+        TimeZoneShow => make_type!(TimeZone -> Text),
 
         ListBuild => make_type!(
             forall (a: Type) ->
@@ -358,6 +373,65 @@ fn apply_builtin(
             }
             _ => Ret::DoneAsIs,
         },
+        (TextReplace, [needle, replacement, haystack]) => {
+            let needle = match &*needle.kind() {
+                TextLit(tlit) => tlit.as_text(),
+                _ => None,
+            };
+            let haystack_text = match &*haystack.kind() {
+                TextLit(tlit) => tlit.as_text(),
+                _ => None,
+            };
+            match (needle, haystack_text) {
+                // Replacing the empty string leaves the haystack untouched
+                (Some(needle), _) if needle.is_empty() => {
+                    Ret::Value(haystack.clone())
+                }
+                (Some(needle), Some(haystack)) => {
+                    // The replacement needs not be a literal; it then
+                    // becomes an interpolation.
+                    let mut parts = haystack.split(needle.as_str());
+                    let first = InterpolatedTextContents::Text(
+                        parts.next().unwrap().to_string(),
+                    );
+                    let rest = parts.flat_map(|part| {
+                        vec![
+                            InterpolatedTextContents::Expr(replacement.clone()),
+                            InterpolatedTextContents::Text(part.to_string()),
+                        ]
+                    });
+                    let tlit = crate::semantics::TextLit::new(
+                        std::iter::once(first).chain(rest),
+                    );
+                    // Simplify bare interpolation
+                    if let Some(v) = tlit.as_single_expr() {
+                        Ret::Value(v.clone())
+                    } else {
+                        Ret::ValueKind(TextLit(tlit))
+                    }
+                }
+                _ => Ret::DoneAsIs,
+            }
+        }
+        (BytesShow, [v]) => match &*v.kind() {
+            BytesLit(b) => Ret::Value(Value::from_text(format!(
+                "0x\"{}\"",
+                hex::encode_upper(b)
+            ))),
+            _ => Ret::DoneAsIs,
+        },
+        (DateShow, [v]) => match &*v.kind() {
+            DateLit(d) => Ret::Value(Value::from_text(d)),
+            _ => Ret::DoneAsIs,
+        },
+        (TimeShow, [v]) => match &*v.kind() {
+            TimeLit(t) => Ret::Value(Value::from_text(t)),
+            _ => Ret::DoneAsIs,
+        },
+        (TimeZoneShow, [v]) => match &*v.kind() {
+            TimeZoneLit(z) => Ret::Value(Value::from_text(z)),
+            _ => Ret::DoneAsIs,
+        },
         (ListLength, [_, l]) => match &*l.kind() {
             EmptyListLit(_) => Ret::ValueKind(NaturalLit(0)),
             NEListLit(xs) => Ret::ValueKind(NaturalLit(xs.len())),
@@ -7,7 +7,7 @@ use crate::semantics::{
     ValueKind,
 };
 use crate::syntax::{
-    BinOp, Builtin, Const, ExprKind, InterpolatedTextContents,
+    BinOp, Builtin, Const, ExprKind, InterpolatedTextContents, Label,
 };
 use crate::Normalized;
 
@@ -238,15 +238,118 @@ fn apply_binop<'a>(
     })
 }
 
+/// `e.(T)` normalizes like `e.{ ks }`, where `ks` are the fields of `T`.
+fn normalize_projection_by_expr(
+    v: Value,
+    t: Value,
+    ty: &Value,
+    env: &NzEnv,
+) -> ValueKind {
+    let labels = match t.kind() {
+        ValueKind::RecordType(kts) => kts.keys().cloned().collect(),
+        _ => return ValueKind::PartialExpr(ExprKind::ProjectionByExpr(v, t)),
+    };
+    normalize_one_layer(ExprKind::Projection(v, labels), ty, env)
+}
+
+/// `showConstructor v` is the name of the alternative that a union value or an `Optional`
+/// holds, if known.
+fn normalize_show_constructor(v: Value, ty: &Value) -> ValueKind {
+    let name = match v.kind() {
+        ValueKind::UnionConstructor(l, _, _)
+        | ValueKind::UnionLit(l, _, _, _, _) => l.into(),
+        ValueKind::NEOptionalLit(_) => "Some".to_owned(),
+        ValueKind::EmptyOptionalLit(_) => "None".to_owned(),
+        _ => return ValueKind::PartialExpr(ExprKind::ShowConstructor(v)),
+    };
+    Value::from_text(name).to_whnf_check_type(ty)
+}
+
+/// `T::r` desugars to `(T.default ⫽ r) : T.Type`.
+fn normalize_completion(
+    t: &Value,
+    r: &Value,
+    ty: &Value,
+    env: &NzEnv,
+) -> ValueKind {
+    let default_label = "default".into();
+    let default_ty = match t.get_type_not_sort().kind() {
+        ValueKind::RecordType(kts) => match kts.get(&default_label) {
+            Some(default_ty) => default_ty.clone(),
+            None => unreachable!("Internal type error"),
+        },
+        _ => unreachable!("Internal type error"),
+    };
+    let default = Value::from_partial_expr(
+        ExprKind::Field(t.clone(), default_label),
+        default_ty,
+    );
+    normalize_one_layer(
+        ExprKind::BinOp(BinOp::RightBiasedRecordMerge, default, r.clone()),
+        ty,
+        env,
+    )
+}
+
+/// `e with k.ks = v` updates the record literal `e`, taking missing intermediate fields to be
+/// `{=}`. Other values of `e` are left as they are.
+fn normalize_with(
+    record: &Value,
+    labels: &[Label],
+    value: &Value,
+    ty: &Value,
+) -> Value {
+    let (first, rest) = match labels.split_first() {
+        Some(split) => split,
+        None => return value.clone(),
+    };
+    let kvs = match record.kind() {
+        ValueKind::RecordLit(kvs) => kvs,
+        _ => {
+            let expr =
+                ExprKind::With(record.clone(), labels.to_vec(), value.clone());
+            return Value::from_kind_and_type(
+                ValueKind::PartialExpr(expr),
+                ty.clone(),
+            );
+        }
+    };
+    let field_ty = match ty.kind() {
+        ValueKind::RecordType(kts) => match kts.get(first) {
+            Some(field_ty) => field_ty.clone(),
+            None => unreachable!("Internal type error"),
+        },
+        _ => unreachable!("Internal type error"),
+    };
+    let field = match kvs.get(first) {
+        Some(field) => normalize_with(field, rest, value, &field_ty),
+        None => {
+            let empty_ty = Value::from_kind_and_type(
+                ValueKind::RecordType(HashMap::new()),
+                Value::from_const(Const::Type),
+            );
+            let empty = Value::from_kind_and_type(
+                ValueKind::RecordLit(HashMap::new()),
+                empty_ty,
+            );
+            normalize_with(&empty, rest, value, &field_ty)
+        }
+    };
+    let mut kvs = kvs.clone();
+    kvs.insert(first.clone(), field);
+    Value::from_kind_and_type(ValueKind::RecordLit(kvs), ty.clone())
+}
+
 pub(crate) fn normalize_one_layer(
     expr: ExprKind<Value, Normalized>,
     ty: &Value,
     env: &NzEnv,
 ) -> ValueKind {
     use ValueKind::{
-        BoolLit, DoubleLit, EmptyListLit, EmptyOptionalLit, IntegerLit,
-        NEListLit, NEOptionalLit, NaturalLit, PartialExpr, RecordLit,
-        RecordType, UnionConstructor, UnionLit, UnionType,
+        BoolLit, BytesLit, DateLit, DoubleLit, EmptyListLit, EmptyOptionalLit,
+        IntegerLit, NEListLit, NEOptionalLit, NaturalLit, PartialExpr,
+        RecordLit, RecordType, TimeLit, TimeZoneLit, UnionConstructor,
+        UnionLit, UnionType,
     };
 
     let ret = match expr {
@@ -271,6 +374,10 @@ pub(crate) fn normalize_one_layer(
         ExprKind::NaturalLit(n) => Ret::ValueKind(NaturalLit(n)),
         ExprKind::IntegerLit(n) => Ret::ValueKind(IntegerLit(n)),
         ExprKind::DoubleLit(n) => Ret::ValueKind(DoubleLit(n)),
+        ExprKind::BytesLit(b) => Ret::ValueKind(BytesLit(b)),
+        ExprKind::DateLit(d) => Ret::ValueKind(DateLit(d)),
+        ExprKind::TimeLit(t) => Ret::ValueKind(TimeLit(t)),
+        ExprKind::TimeZoneLit(z) => Ret::ValueKind(TimeZoneLit(z)),
         ExprKind::SomeLit(e) => Ret::ValueKind(NEOptionalLit(e)),
         ExprKind::EmptyListLit(t) => {
             let arg = match t.kind() {
@@ -351,6 +458,13 @@ pub(crate) fn normalize_one_layer(
                 kts.clone(),
                 v.get_type().unwrap(),
             )),
+            PartialExpr(ExprKind::Projection(v2, _)) => {
+                return normalize_one_layer(
+                    ExprKind::Field(v2.clone(), l.clone()),
+                    ty,
+                    env,
+                )
+            }
             PartialExpr(ExprKind::BinOp(
                 BinOp::RightBiasedRecordMerge,
                 x,
@@ -435,10 +549,16 @@ pub(crate) fn normalize_one_layer(
             },
             _ => Ret::Expr(expr),
         },
-        ExprKind::ProjectionByExpr(_, _) => {
-            unimplemented!("selection by expression")
+        ExprKind::ProjectionByExpr(v, t) => {
+            return normalize_projection_by_expr(v, t, ty, env)
+        }
+        ExprKind::Completion(ref t, ref r) => {
+            return normalize_completion(t, r, ty, env)
+        }
+        ExprKind::With(record, labels, value) => {
+            return normalize_with(&record, &labels, &value, ty)
+                .to_whnf_check_type(ty)
         }
-        ExprKind::Completion(_, _) => unimplemented!("record completion"),
 
         ExprKind::Merge(ref handlers, ref variant, _) => {
             match handlers.kind() {
@@ -464,6 +584,9 @@ pub(crate) fn normalize_one_layer(
                 _ => Ret::Expr(expr),
             }
         }
+        ExprKind::ShowConstructor(v) => {
+            return normalize_show_constructor(v, ty)
+        }
         ExprKind::ToMap(ref v, ref annot) => match v.kind() {
             RecordLit(kvs) if kvs.is_empty() => {
                 match annot.as_ref().map(|v| v.kind()) {
@@ -483,7 +606,10 @@ pub(crate) fn normalize_one_layer(
                     .map(|(k, v)| {
                         let mut rec = HashMap::new();
                         let mut rec_ty = HashMap::new();
-                        rec.insert("mapKey".into(), Value::from_text(k));
+                        rec.insert(
+                            "mapKey".into(),
+                            Value::from_text(k.as_ref()),
+                        );
                         rec.insert("mapValue".into(), v.clone());
                         rec_ty.insert(
                             "mapKey".into(),
@@ -11,8 +11,8 @@ use crate::semantics::{
 use crate::semantics::{type_of_builtin, typecheck, TyExpr, TyExprKind};
 use crate::semantics::{BuiltinClosure, NzEnv, NzVar, VarEnv};
 use crate::syntax::{
-    BinOp, Builtin, Const, ExprKind, Integer, InterpolatedTextContents, Label,
-    NaiveDouble, Natural, Span,
+    BinOp, Builtin, Const, Date, ExprKind, Integer, InterpolatedTextContents,
+    Label, NaiveDouble, Natural, Span, Time, TimeZone,
 };
 use crate::{Normalized, NormalizedExpr, ToExprOptions};
 
@@ -92,6 +92,10 @@ pub(crate) enum ValueKind {
     NaturalLit(Natural),
     IntegerLit(Integer),
     DoubleLit(NaiveDouble),
+    BytesLit(Vec<u8>),
+    DateLit(Date),
+    TimeLit(Time),
+    TimeZoneLit(TimeZone),
     EmptyOptionalLit(Value),
     NEOptionalLit(Value),
     // EmptyListLit(t) means `[] : List t`, not `[] : t`
@@ -296,6 +300,10 @@ impl Value {
                 ValueKind::NaturalLit(n) => ExprKind::NaturalLit(*n),
                 ValueKind::IntegerLit(n) => ExprKind::IntegerLit(*n),
                 ValueKind::DoubleLit(n) => ExprKind::DoubleLit(*n),
+                ValueKind::BytesLit(b) => ExprKind::BytesLit(b.clone()),
+                ValueKind::DateLit(d) => ExprKind::DateLit(*d),
+                ValueKind::TimeLit(t) => ExprKind::TimeLit(t.clone()),
+                ValueKind::TimeZoneLit(z) => ExprKind::TimeZoneLit(*z),
                 ValueKind::EmptyOptionalLit(n) => ExprKind::App(
                     Value::from_builtin(Builtin::OptionalNone).to_tyexpr(venv),
                     n.to_tyexpr(venv),
@@ -421,7 +429,11 @@ impl ValueKind {
             | ValueKind::BoolLit(_)
             | ValueKind::NaturalLit(_)
             | ValueKind::IntegerLit(_)
-            | ValueKind::DoubleLit(_) => {}
+            | ValueKind::DoubleLit(_)
+            | ValueKind::BytesLit(_)
+            | ValueKind::DateLit(_)
+            | ValueKind::TimeLit(_)
+            | ValueKind::TimeZoneLit(_) => {}
 
             ValueKind::EmptyOptionalLit(tth) | ValueKind::EmptyListLit(tth) => {
                 tth.normalize();
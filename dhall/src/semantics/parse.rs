@@ -1,18 +1,18 @@
 use std::fs::File;
-use std::io::Read;
-use std::path::Path;
+use std::io::{Error as IOError, ErrorKind, Read};
+use std::path::{Path, PathBuf};
 
 use crate::error::Error;
 use crate::semantics::resolve::ImportRoot;
 use crate::syntax::binary;
-use crate::syntax::parse_expr;
+use crate::syntax::{parse_expr, parse_expr_with_origin};
 use crate::Parsed;
 
 pub(crate) fn parse_file(f: &Path) -> Result<Parsed, Error> {
     let mut buffer = String::new();
     File::open(f)?.read_to_string(&mut buffer)?;
-    let expr = parse_expr(&*buffer)?;
-    let root = ImportRoot::LocalDir(f.parent().unwrap().to_owned());
+    let expr = parse_expr_with_origin(&*buffer, f.display().to_string())?;
+    let root = ImportRoot::LocalDir(dir_of(f)?);
     Ok(Parsed(expr, root))
 }
 
@@ -32,6 +32,17 @@ pub(crate) fn parse_binary_file(f: &Path) -> Result<Parsed, Error> {
     let mut buffer = Vec::new();
     File::open(f)?.read_to_end(&mut buffer)?;
     let expr = binary::decode(&buffer)?;
-    let root = ImportRoot::LocalDir(f.parent().unwrap().to_owned());
+    let root = ImportRoot::LocalDir(dir_of(f)?);
     Ok(Parsed(expr, root))
 }
+
+/// The absolute directory containing `f`.
+fn dir_of(f: &Path) -> Result<PathBuf, Error> {
+    let dir = f.parent().ok_or_else(|| {
+        IOError::new(
+            ErrorKind::InvalidInput,
+            format!("`{}` is not a file path", f.display()),
+        )
+    })?;
+    Ok(std::env::current_dir()?.join(dir))
+}
@@ -0,0 +1,78 @@
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+use crate::{Normalized, Parsed};
+
+/// Distinguishes the temporary files of concurrent writers within a process.
+static TMP_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// The semantic cache: a directory of binary-encoded normalized expressions, indexed by their
+/// sha256 hash. Hashed imports found there don't need to be fetched again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    /// A cache stored in `dir`. The directory is created when the first entry is written.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Cache { dir: dir.into() }
+    }
+
+    /// The standard cache location: `$XDG_CACHE_HOME/dhall`, or `~/.cache/dhall` if
+    /// `XDG_CACHE_HOME` is not set.
+    pub fn from_env() -> Option<Self> {
+        let base = match std::env::var_os("XDG_CACHE_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(std::env::var_os("HOME")?).join(".cache"),
+        };
+        Some(Cache::new(base.join("dhall")))
+    }
+
+    fn entry_path(&self, hash: &[u8]) -> PathBuf {
+        self.dir.join(format!("1220{}", hex::encode(hash)))
+    }
+
+    /// Look up the expression with the given hash. Missing, unreadable or corrupted entries are
+    /// treated as absent.
+    pub(crate) fn get(&self, hash: &[u8]) -> Option<Normalized> {
+        let mut data = Vec::new();
+        File::open(self.entry_path(hash))
+            .ok()?
+            .read_to_end(&mut data)
+            .ok()?;
+        if Sha256::digest(&data).as_slice() != hash {
+            return None;
+        }
+        let load = || -> Result<Normalized, Error> {
+            Ok(Parsed::parse_binary(&data)?
+                .skip_resolve()?
+                .typecheck()?
+                .normalize())
+        };
+        load().ok()
+    }
+
+    /// Store the binary encoding of an alpha-normalized expression under its hash. Failing to
+    /// write to the cache is not an error.
+    pub(crate) fn insert(&self, hash: &[u8], data: &[u8]) {
+        let write = || -> std::io::Result<()> {
+            fs::create_dir_all(&self.dir)?;
+            // Write to a temporary file first so that readers never see a partial entry
+            let path = self.entry_path(hash);
+            let tmp_path = path.with_extension(format!(
+                "{}-{}.tmp",
+                std::process::id(),
+                TMP_COUNT.fetch_add(1, Ordering::SeqCst)
+            ));
+            File::create(&tmp_path)?.write_all(data)?;
+            fs::rename(tmp_path, path)
+        };
+        let _ = write();
+    }
+}
@@ -0,0 +1,25 @@
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+
+/// Provides the values of `env:` imports.
+pub trait Environment {
+    /// Look up an environment variable. Returns `None` if it is not set.
+    fn var(&self, name: &str) -> Option<String>;
+}
+
+/// The environment of the current process.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessEnvironment;
+
+impl Environment for ProcessEnvironment {
+    fn var(&self, name: &str) -> Option<String> {
+        std::env::var(name).ok()
+    }
+}
+
+/// A fixed set of variables.
+impl<S: BuildHasher> Environment for HashMap<String, String, S> {
+    fn var(&self, name: &str) -> Option<String> {
+        self.get(name).cloned()
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::io::{Error as IOError, ErrorKind};
+
+/// The result of fetching a remote import.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FetchResponse {
+    /// The raw contents of the response.
+    pub body: Vec<u8>,
+    /// The response headers, as `(name, value)` pairs.
+    pub headers: Vec<(String, String)>,
+}
+
+/// Retrieves the contents of remote (`http://` and `https://`) imports.
+pub trait Fetcher {
+    /// Fetch `url`, sending the given request headers. The headers come from the evaluated
+    /// `using` expression of the import, if any.
+    fn fetch(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<FetchResponse, IOError>;
+}
+
+impl FetchResponse {
+    pub fn new(body: impl Into<Vec<u8>>) -> Self {
+        FetchResponse {
+            body: body.into(),
+            headers: Vec::new(),
+        }
+    }
+
+    /// Add a response header.
+    pub fn with_header(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Look up a response header. Header names are case-insensitive.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// A fetcher that serves a fixed set of urls from memory. Mostly useful for tests.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFetcher {
+    files: HashMap<String, FetchResponse>,
+}
+
+impl MemoryFetcher {
+    pub fn new() -> Self {
+        MemoryFetcher::default()
+    }
+
+    /// Serve `body` at `url`.
+    pub fn insert(&mut self, url: impl Into<String>, body: impl Into<Vec<u8>>) {
+        self.insert_response(url, FetchResponse::new(body))
+    }
+
+    /// Serve `response` at `url`.
+    pub fn insert_response(
+        &mut self,
+        url: impl Into<String>,
+        response: FetchResponse,
+    ) {
+        self.files.insert(url.into(), response);
+    }
+}
+
+impl Fetcher for MemoryFetcher {
+    fn fetch(
+        &self,
+        url: &str,
+        _headers: &[(String, String)],
+    ) -> Result<FetchResponse, IOError> {
+        match self.files.get(url) {
+            Some(response) => Ok(response.clone()),
+            None => Err(IOError::new(
+                ErrorKind::NotFound,
+                format!("no such url: {}", url),
+            )),
+        }
+    }
+}
+
+/// A fetcher that refuses every request.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoFetcher;
+
+impl Fetcher for NoFetcher {
+    fn fetch(
+        &self,
+        url: &str,
+        _headers: &[(String, String)],
+    ) -> Result<FetchResponse, IOError> {
+        Err(IOError::new(
+            ErrorKind::Other,
+            format!("remote imports are not available: {}", url),
+        ))
+    }
+}
+
+/// Fetches remote imports over HTTP(S).
+#[cfg(feature = "http")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HttpFetcher;
+
+#[cfg(feature = "http")]
+impl Fetcher for HttpFetcher {
+    fn fetch(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<FetchResponse, IOError> {
+        use std::io::Read;
+
+        let mut request = ureq::get(url);
+        for (name, value) in headers {
+            request.set(name, value);
+        }
+        let response = request.call();
+        if let Some(err) = response.synthetic_error() {
+            return Err(IOError::new(
+                ErrorKind::Other,
+                format!("failed to fetch {}: {}", url, err),
+            ));
+        }
+        if !response.ok() {
+            return Err(IOError::new(
+                ErrorKind::Other,
+                format!(
+                    "failed to fetch {}: {} {}",
+                    url,
+                    response.status(),
+                    response.status_text()
+                ),
+            ));
+        }
+
+        let headers = response
+            .headers_names()
+            .into_iter()
+            .filter_map(|name| {
+                let value = response.header(&name)?.to_string();
+                Some((name, value))
+            })
+            .collect();
+        let mut body = Vec::new();
+        response.into_reader().read_to_end(&mut body)?;
+        Ok(FetchResponse { body, headers })
+    }
+}
+
+/// The fetcher used when none is specified.
+#[cfg(feature = "http")]
+pub(crate) type DefaultFetcher = HttpFetcher;
+#[cfg(not(feature = "http"))]
+pub(crate) type DefaultFetcher = NoFetcher;
@@ -0,0 +1,11 @@
+pub mod cache;
+pub mod env;
+pub mod fetch;
+pub mod policy;
+#[allow(clippy::module_inception)]
+pub mod resolve;
+pub use cache::*;
+pub use env::*;
+pub use fetch::*;
+pub use policy::*;
+pub use resolve::*;
@@ -0,0 +1,107 @@
+use std::path::PathBuf;
+
+/// Which imports may be resolved. Everything is allowed by default.
+///
+/// `missing` and `as Location` imports never read anything and are always allowed.
+///
+/// A forbidden import fails with `ImportError::Forbidden`. Like other resolution errors, this
+/// falls back to the right side of `?`: with `allow_remote(false)`,
+/// `https://example.com/x ? 2` resolves to `2`.
+///
+/// The policy is checked before the semantic cache is read: a forbidden hashed import is rejected
+/// even if it is cached, so whether an expression is accepted doesn't depend on what happens to be
+/// in the cache.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportPolicy {
+    pub(crate) local: bool,
+    pub(crate) remote: bool,
+    pub(crate) env: bool,
+    /// If set, local imports must be inside this directory.
+    pub(crate) local_root: Option<PathBuf>,
+    /// The maximum number of nested imports.
+    pub(crate) max_depth: Option<usize>,
+    /// The maximum number of distinct imports.
+    pub(crate) max_imports: Option<usize>,
+}
+
+impl ImportPolicy {
+    /// A policy that allows every import.
+    pub fn new() -> Self {
+        ImportPolicy {
+            local: true,
+            remote: true,
+            env: true,
+            local_root: None,
+            max_depth: None,
+            max_imports: None,
+        }
+    }
+
+    /// Whether to allow importing local files.
+    pub fn allow_local(mut self, allow: bool) -> Self {
+        self.local = allow;
+        self
+    }
+
+    /// Whether to allow `http://` and `https://` imports.
+    pub fn allow_remote(mut self, allow: bool) -> Self {
+        self.remote = allow;
+        self
+    }
+
+    /// Whether to allow `env:` imports.
+    pub fn allow_env(mut self, allow: bool) -> Self {
+        self.env = allow;
+        self
+    }
+
+    /// Only allow local imports of files inside `dir`. A relative `dir` is relative to the
+    /// current directory. Paths are compared after removing `.` and `..`; symbolic links are not
+    /// followed.
+    pub fn local_root(mut self, dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        let dir = match std::env::current_dir() {
+            Ok(cwd) => cwd.join(dir),
+            Err(_) => dir,
+        };
+        self.local_root = Some(normalize_path(&dir));
+        self
+    }
+
+    /// Allow at most `depth` levels of nested imports. An import written in the top-level
+    /// expression has depth 1.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Allow at most `count` distinct imports in total. Importing the same file twice counts
+    /// once. `as Location` imports and hashed imports found in the semantic cache read nothing,
+    /// but still count towards the total.
+    pub fn max_imports(mut self, count: usize) -> Self {
+        self.max_imports = Some(count);
+        self
+    }
+}
+
+impl Default for ImportPolicy {
+    fn default() -> Self {
+        ImportPolicy::new()
+    }
+}
+
+/// Remove `.` and `..` components without touching the filesystem.
+pub(crate) fn normalize_path(path: &std::path::Path) -> PathBuf {
+    use std::path::Component;
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            c => normalized.push(c.as_os_str()),
+        }
+    }
+    normalized
+}
@@ -0,0 +1,813 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, ImportError};
+use crate::semantics::resolve::{
+    normalize_path, Cache, DefaultFetcher, Environment, FetchResponse, Fetcher,
+    ImportPolicy, ProcessEnvironment,
+};
+use crate::semantics::Value;
+use crate::syntax;
+use crate::syntax::binary;
+use crate::syntax::map::DupTreeMap;
+use crate::syntax::{
+    parse_expr_with_origin, BinOp, Builtin, Expr, ExprKind, FilePath,
+    FilePrefix, Hash, ImportLocation, ImportMode, Span, URL,
+};
+use crate::{Normalized, NormalizedExpr, Parsed, Resolved};
+
+type Import = syntax::Import<NormalizedExpr>;
+
+/// A root from which to resolve relative imports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ImportRoot {
+    LocalDir(PathBuf),
+    /// The url of a remote file. Relative imports inside it become urls on the same server and
+    /// inherit its headers.
+    Remote(URL<NormalizedExpr>),
+}
+
+/// Controls how imports are resolved: which imports are allowed, what relative imports are
+/// relative to, where remote imports are fetched from, where environment variables are read
+/// from, what `~` refers to and where hashed imports are cached.
+pub struct ImportResolver {
+    fetcher: Box<dyn Fetcher + Send + Sync>,
+    env: Box<dyn Environment + Send + Sync>,
+    home_dir: Option<PathBuf>,
+    root_dir: Option<PathBuf>,
+    cache: Option<Cache>,
+    policy: ImportPolicy,
+}
+
+impl ImportResolver {
+    /// A resolver that fetches remote imports with the default fetcher and reads the environment
+    /// of the current process.
+    pub fn new() -> Self {
+        ImportResolver {
+            fetcher: Box::new(DefaultFetcher::default()),
+            env: Box::new(ProcessEnvironment),
+            home_dir: std::env::var_os("HOME")
+                .or_else(|| std::env::var_os("USERPROFILE"))
+                .map(PathBuf::from),
+            root_dir: None,
+            cache: Cache::from_env(),
+            policy: ImportPolicy::new(),
+        }
+    }
+
+    /// Resolve the relative imports of the top-level expression against `dir`, instead of the
+    /// directory of the file it was parsed from, or the current directory for strings. Relative
+    /// imports inside `env:` imports are resolved against `dir` too. A relative `dir` is relative
+    /// to the current directory.
+    pub fn root_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        self.root_dir = Some(match std::env::current_dir() {
+            Ok(cwd) => cwd.join(dir),
+            Err(_) => dir,
+        });
+        self
+    }
+
+    /// Fetch remote imports with `fetcher`.
+    pub fn fetcher(
+        mut self,
+        fetcher: impl Fetcher + Send + Sync + 'static,
+    ) -> Self {
+        self.fetcher = Box::new(fetcher);
+        self
+    }
+
+    /// Read `env:` imports from `env` instead of the process environment.
+    pub fn env(
+        mut self,
+        env: impl Environment + Send + Sync + 'static,
+    ) -> Self {
+        self.env = Box::new(env);
+        self
+    }
+
+    /// Resolve `~/` imports relative to `home_dir` instead of `$HOME`.
+    pub fn home_dir(mut self, home_dir: impl Into<PathBuf>) -> Self {
+        self.home_dir = Some(home_dir.into());
+        self
+    }
+
+    /// Store the semantic cache in `dir` instead of `$XDG_CACHE_HOME/dhall`.
+    pub fn cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache = Some(Cache::new(dir));
+        self
+    }
+
+    /// Neither read from nor write to the semantic cache.
+    pub fn no_cache(mut self) -> Self {
+        self.cache = None;
+        self
+    }
+
+    /// Only resolve the imports allowed by `policy`. Other imports fail with
+    /// `ImportError::Forbidden`.
+    pub fn policy(mut self, policy: ImportPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+impl Default for ImportResolver {
+    fn default() -> Self {
+        ImportResolver::new()
+    }
+}
+
+impl std::fmt::Debug for ImportResolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ImportResolver")
+            .field("home_dir", &self.home_dir)
+            .field("root_dir", &self.root_dir)
+            .field("cache", &self.cache)
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+/// Maps chained imports to their result.
+type ImportCache = HashMap<Import, Normalized>;
+
+/// The chained imports currently being resolved.
+pub(crate) type ImportStack = Vec<Import>;
+
+/// Chain an import onto the root it was found in. The location of the resulting import no longer
+/// depends on where it was found: local paths are absolute or relative to `~`, relative imports
+/// inside a remote file become urls, and headers are evaluated.
+fn chain_import(root: &ImportRoot, import: &Import) -> Result<Import, Error> {
+    use syntax::FilePrefix::*;
+    use syntax::ImportLocation::*;
+    let location = match (root, &import.location) {
+        (_, Remote(url)) => Remote(URL {
+            scheme: url.scheme,
+            authority: url.authority.clone(),
+            path: url.path.canonicalize(),
+            query: url.query.clone(),
+            headers: eval_headers(url)?,
+        }),
+        (_, Local(Home, path)) if allowed_from(root, import) => {
+            Local(Home, path.canonicalize())
+        }
+        (_, Local(Absolute, path)) if allowed_from(root, import) => {
+            Local(Absolute, absolute_path(path.canonicalize()))
+        }
+        (ImportRoot::LocalDir(dir), Local(prefix, path)) => {
+            let (drive, dir) = path_to_file_path(dir);
+            let path = match prefix {
+                Here => join_paths(&dir, path),
+                _ => join_paths(&dir, &parent_of(path)),
+            };
+            let mut path = absolute_path(path.canonicalize());
+            path.file_path.splice(0..0, drive);
+            Local(Absolute, path)
+        }
+        (ImportRoot::Remote(parent), Local(prefix, path)) => {
+            let dir = FilePath {
+                file_path: match parent.path.file_path.split_last() {
+                    Some((_, dir)) => dir.to_vec(),
+                    None => Vec::new(),
+                },
+            };
+            let path = match prefix {
+                Here => join_paths(&dir, path),
+                Parent => join_paths(&dir, &parent_of(path)),
+                Absolute | Home => {
+                    return Err(ImportError::SanityCheck(import.clone()).into())
+                }
+            };
+            Remote(URL {
+                scheme: parent.scheme,
+                authority: parent.authority.clone(),
+                path: path.canonicalize(),
+                query: None,
+                headers: parent.headers.clone(),
+            })
+        }
+        (_, Env(_)) if !allowed_from(root, import) => {
+            return Err(ImportError::SanityCheck(import.clone()).into())
+        }
+        (_, Env(_)) | (_, Missing) => return Ok(import.clone()),
+    };
+    Ok(Import {
+        mode: import.mode,
+        location,
+        hash: import.hash.clone(),
+    })
+}
+
+/// Referential sanity: remote files may not read local files or environment variables. They
+/// may still ask for their location.
+fn allowed_from(root: &ImportRoot, import: &Import) -> bool {
+    match root {
+        ImportRoot::LocalDir(_) => true,
+        ImportRoot::Remote(_) => import.mode == ImportMode::Location,
+    }
+}
+
+fn join_paths(dir: &FilePath, path: &FilePath) -> FilePath {
+    FilePath {
+        file_path: dir
+            .file_path
+            .iter()
+            .chain(path.file_path.iter())
+            .cloned()
+            .collect(),
+    }
+}
+
+fn parent_of(path: &FilePath) -> FilePath {
+    let mut file_path = vec!["..".to_string()];
+    file_path.extend(path.file_path.iter().cloned());
+    FilePath { file_path }
+}
+
+/// Leading `..` components of an absolute path point to the root itself.
+fn absolute_path(path: FilePath) -> FilePath {
+    FilePath {
+        file_path: path
+            .file_path
+            .into_iter()
+            .skip_while(|c| c == "..")
+            .collect(),
+    }
+}
+
+/// Split an absolute path into its prefix on Windows, e.g. a drive letter, and its components.
+fn path_to_file_path(path: &Path) -> (Option<String>, FilePath) {
+    let mut prefix = None;
+    let mut file_path = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Prefix(p) => {
+                prefix = Some(p.as_os_str().to_string_lossy().into_owned())
+            }
+            Component::Normal(c) => {
+                file_path.push(c.to_string_lossy().into_owned())
+            }
+            Component::ParentDir => file_path.push("..".to_string()),
+            Component::RootDir | Component::CurDir => {}
+        }
+    }
+    (prefix, FilePath { file_path })
+}
+
+/// The path of a chained local import. On Windows, its first component may be a prefix.
+fn file_path_to_path(path: &FilePath) -> PathBuf {
+    let mut components = path.file_path.iter().peekable();
+    let mut path_buf = PathBuf::new();
+    if let Some(first) = components.peek() {
+        if let Some(Component::Prefix(_)) = Path::new(first).components().next()
+        {
+            path_buf.push(first);
+            components.next();
+        }
+    }
+    path_buf.push(Component::RootDir);
+    path_buf.extend(components);
+    path_buf
+}
+
+/// Typecheck and normalize the headers of a remote import.
+fn eval_headers(
+    url: &URL<NormalizedExpr>,
+) -> Result<Option<NormalizedExpr>, Error> {
+    let headers = match &url.headers {
+        Some(headers) => headers,
+        None => return Ok(None),
+    };
+    let text = Normalized::make_builtin_type(Builtin::Text);
+    let header_type = Normalized::make_list_type(Normalized::make_record_type(
+        vec![
+            ("mapKey".to_string(), text.clone()),
+            ("mapValue".to_string(), text),
+        ]
+        .into_iter(),
+    ));
+    let headers = Resolved(headers.clone())
+        .typecheck_with(&header_type)?
+        .normalize();
+    Ok(Some(headers.to_expr()))
+}
+
+/// Extract the `(name, value)` pairs from evaluated headers.
+fn header_pairs(headers: &NormalizedExpr) -> Vec<(String, String)> {
+    let text = |e: &NormalizedExpr| match e.as_ref() {
+        ExprKind::TextLit(t) if t.tail().is_empty() => t.head().to_string(),
+        _ => unreachable!("headers must be normalized"),
+    };
+    match headers.as_ref() {
+        ExprKind::NEListLit(entries) => entries
+            .iter()
+            .map(|entry| match entry.as_ref() {
+                ExprKind::RecordLit(kvs) => {
+                    let field = |name: &str| {
+                        kvs.iter()
+                            .find(|(k, _)| k.as_ref() == name)
+                            .map(|(_, v)| text(v))
+                            .unwrap()
+                    };
+                    (field("mapKey"), field("mapValue"))
+                }
+                _ => unreachable!("headers must be normalized"),
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn url_to_string<SE>(url: &URL<SE>) -> String {
+    let mut s = format!(
+        "{}://{}/{}",
+        url.scheme,
+        url.authority,
+        url.path.file_path.join("/")
+    );
+    if let Some(q) = &url.query {
+        s.push('?');
+        s.push_str(q);
+    }
+    s
+}
+
+fn url_origin<SE>(url: &URL<SE>) -> String {
+    let host = match url.authority.rfind('@') {
+        Some(i) => &url.authority[i + 1..],
+        None => &url.authority,
+    };
+    format!("{}://{}", url.scheme, host)
+}
+
+/// A remote file may only import a file from another origin if that origin allows it.
+fn check_cors(
+    parent: &URL<NormalizedExpr>,
+    import: &Import,
+    url: &URL<NormalizedExpr>,
+    response: &FetchResponse,
+) -> Result<(), ImportError> {
+    let origin = url_origin(parent);
+    if origin == url_origin(url) {
+        return Ok(());
+    }
+    match response.header("Access-Control-Allow-Origin") {
+        Some(allowed) if allowed.trim() == "*" || allowed.trim() == origin => {
+            Ok(())
+        }
+        _ => Err(ImportError::CorsDisallowed(origin, import.clone())),
+    }
+}
+
+fn resolve_import(
+    import: &Import,
+    chained: &Import,
+    root: &ImportRoot,
+    resolver: &ImportResolver,
+    import_cache: &mut ImportCache,
+    import_stack: &ImportStack,
+) -> Result<Normalized, ImportError> {
+    use syntax::ImportLocation::*;
+    if chained.mode == ImportMode::Location {
+        return Ok(location_value(chained).map_err(|e| {
+            ImportError::Recursive(import.clone(), Box::new(e))
+        })?);
+    }
+    // Failures to locate the import are reported as-is, so that `?` can catch them.
+    let (bytes, import_root) = match &chained.location {
+        Local(prefix, path) => match local_file_path(resolver, *prefix, path) {
+            Some(path) => read_local(&path),
+            None => return Err(ImportError::NoHomeDir),
+        },
+        Remote(url) => read_remote(chained, url, root, resolver),
+        Env(name) => match resolver.env.var(name) {
+            // Relative imports in environment variables are relative to the root directory.
+            Some(value) => match &resolver.root_dir {
+                Some(dir) => Ok(dir.clone()),
+                None => std::env::current_dir().map_err(Error::from),
+            }
+            .map(|dir| (value.into_bytes(), ImportRoot::LocalDir(dir))),
+            None => return Err(ImportError::MissingEnvVar(import.clone())),
+        },
+        Missing => return Err(ImportError::Missing),
+    }
+    .map_err(|e| ImportError::Recursive(import.clone(), Box::new(e)))?;
+    let text = String::from_utf8(bytes).map_err(|e| {
+        let err = ImportError::InvalidUtf8(e.utf8_error());
+        ImportError::Recursive(import.clone(), Box::new(err.into()))
+    })?;
+    let result = match chained.mode {
+        ImportMode::RawText => {
+            Ok(Normalized::from_value(Value::from_text(text)))
+        }
+        _ => load_code(
+            &text,
+            import_origin(chained),
+            import_root,
+            resolver,
+            import_cache,
+            import_stack,
+        ),
+    };
+    Ok(result
+        .map_err(|e| ImportError::Recursive(import.clone(), Box::new(e)))?)
+}
+
+/// A description of where an import comes from, used in error messages. Local files are shown
+/// relative to the current directory when they share a parent with it, so this is computed when
+/// the import is loaded rather than when an error is displayed.
+pub(crate) fn import_origin(import: &Import) -> String {
+    if let ImportLocation::Local(FilePrefix::Absolute, path) = &import.location
+    {
+        let path = file_path_to_path(path);
+        if let Ok(dir) = std::env::current_dir() {
+            let dir: Vec<_> = dir.components().collect();
+            let file: Vec<_> = path.components().collect();
+            let common =
+                dir.iter().zip(&file).take_while(|(x, y)| x == y).count();
+            // Only the root is shared: the absolute path is clearer.
+            if common <= 1 {
+                return path.display().to_string();
+            }
+            let mut relative = PathBuf::new();
+            relative.extend(dir[common..].iter().map(|_| Component::ParentDir));
+            relative.extend(&file[common..]);
+            return relative.display().to_string();
+        }
+    }
+    let location: Import = syntax::Import {
+        mode: ImportMode::Code,
+        location: import.location.clone(),
+        hash: None,
+    };
+    location.to_string()
+}
+
+/// The value of an `as Location` import: a union describing where the chained import points to.
+fn location_value(import: &Import) -> Result<Normalized, Error> {
+    use syntax::ImportLocation::*;
+    let mk = |kind| Expr::new(kind, Span::Artificial);
+    let text_type = || mk(ExprKind::Builtin(Builtin::Text));
+    let mut alternatives = DupTreeMap::new();
+    alternatives.insert("Local".into(), Some(text_type()));
+    alternatives.insert("Remote".into(), Some(text_type()));
+    alternatives.insert("Environment".into(), Some(text_type()));
+    alternatives.insert("Missing".into(), None);
+    let location_type = mk(ExprKind::UnionType(alternatives));
+
+    let (alternative, text) = match &import.location {
+        Local(prefix, path) => {
+            let local: Import = syntax::Import {
+                mode: ImportMode::Code,
+                location: ImportLocation::Local(*prefix, path.clone()),
+                hash: None,
+            };
+            ("Local", Some(local.to_string()))
+        }
+        Remote(url) => ("Remote", Some(url_to_string(url))),
+        Env(name) => ("Environment", Some(name.clone())),
+        Missing => ("Missing", None),
+    };
+    let mut expr = mk(ExprKind::Field(location_type, alternative.into()));
+    if let Some(text) = text {
+        let text = mk(ExprKind::TextLit(text.into()));
+        expr = mk(ExprKind::App(expr, text));
+    }
+    Ok(Resolved(expr).typecheck()?.normalize())
+}
+
+/// The file a chained local import points to. Returns `None` for `~/` imports if the home
+/// directory is unknown.
+fn local_file_path(
+    resolver: &ImportResolver,
+    prefix: FilePrefix,
+    path: &FilePath,
+) -> Option<PathBuf> {
+    match prefix {
+        FilePrefix::Home => {
+            let mut path_buf = resolver.home_dir.clone()?;
+            path_buf.extend(&path.file_path);
+            Some(path_buf)
+        }
+        FilePrefix::Absolute => Some(file_path_to_path(path)),
+        _ => unreachable!("chained local imports are not relative"),
+    }
+}
+
+/// Read a local file, returning its contents and the root for the imports it contains.
+fn read_local(path: &Path) -> Result<(Vec<u8>, ImportRoot), Error> {
+    let bytes = std::fs::read(path)?;
+    let dir = path.parent().unwrap_or(path).to_path_buf();
+    Ok((bytes, ImportRoot::LocalDir(dir)))
+}
+
+/// Fetch a remote file, returning its contents and the root for the imports it contains.
+fn read_remote(
+    import: &Import,
+    url: &URL<NormalizedExpr>,
+    root: &ImportRoot,
+    resolver: &ImportResolver,
+) -> Result<(Vec<u8>, ImportRoot), Error> {
+    let headers = match &url.headers {
+        Some(headers) => header_pairs(headers),
+        None => Vec::new(),
+    };
+    let response = resolver.fetcher.fetch(&url_to_string(url), &headers)?;
+    if let ImportRoot::Remote(parent) = root {
+        check_cors(parent, import, url, &response)?;
+    }
+    Ok((response.body, ImportRoot::Remote(url.clone())))
+}
+
+fn load_code(
+    text: &str,
+    origin: String,
+    root: ImportRoot,
+    resolver: &ImportResolver,
+    import_cache: &mut ImportCache,
+    import_stack: &ImportStack,
+) -> Result<Normalized, Error> {
+    let parsed = Parsed(parse_expr_with_origin(text, origin)?, root);
+    Ok(
+        do_resolve_expr(parsed, resolver, import_cache, import_stack)?
+            .typecheck()
+            .map_err(|e| e.with_import_stack(import_stack))?
+            .normalize(),
+    )
+}
+
+/// Whether the failure of the left side of `?` lets resolution fall back to the right side. Only
+/// resolution errors do; type errors and import cycles are reported as they are.
+fn allows_fallback(err: &ImportError) -> bool {
+    match err {
+        ImportError::Recursive(_, err) => match err.as_ref() {
+            Error::Resolve(err) => allows_fallback(err),
+            Error::Typecheck(_) => false,
+            _ => true,
+        },
+        ImportError::UnexpectedImport(_) | ImportError::ImportCycle(_, _) => {
+            false
+        }
+        _ => true,
+    }
+}
+
+/// Replace each import in `expr` with the result of `f`, trying the alternatives of `?` in order.
+fn traverse_resolve_expr(
+    expr: &mut NormalizedExpr,
+    f: &mut impl FnMut(Import) -> Result<Normalized, ImportError>,
+) -> Result<(), ImportError> {
+    let garbage_expr = ExprKind::BoolLit(false);
+    match expr.as_mut() {
+        ExprKind::BinOp(BinOp::ImportAlt, l, r) => {
+            let new_self = match traverse_resolve_expr(l, f) {
+                Ok(()) => l,
+                Err(e) if !allows_fallback(&e) => return Err(e),
+                Err(left_err) => match traverse_resolve_expr(r, f) {
+                    Ok(()) => r,
+                    Err(e) if !allows_fallback(&e) => return Err(e),
+                    Err(right_err) => {
+                        let mut errs = Vec::new();
+                        for err in vec![left_err, right_err] {
+                            match err {
+                                ImportError::MissingImports(es) => {
+                                    errs.extend(es)
+                                }
+                                err => errs.push(err),
+                            }
+                        }
+                        return Err(ImportError::MissingImports(errs));
+                    }
+                },
+            };
+            let new_kind = std::mem::replace(new_self.as_mut(), garbage_expr);
+            *expr.as_mut() = new_kind;
+        }
+        kind => {
+            kind.traverse_mut(|e| traverse_resolve_expr(e, f))?;
+            if let ExprKind::Import(import) = kind {
+                let garbage_import = syntax::Import {
+                    mode: ImportMode::Code,
+                    location: ImportLocation::Missing,
+                    hash: None,
+                };
+                // Move out of &mut import
+                let import = std::mem::replace(import, garbage_import);
+                *kind = ExprKind::Embed(f(import)?);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check that the policy of `resolver` allows resolving the chained import `import`.
+fn check_policy(
+    resolver: &ImportResolver,
+    import: &Import,
+    import_cache: &ImportCache,
+    import_stack: &ImportStack,
+) -> Result<(), ImportError> {
+    use syntax::ImportLocation::*;
+    let policy = &resolver.policy;
+    let allowed = match &import.location {
+        _ if import.mode == ImportMode::Location => return Ok(()),
+        Missing => return Ok(()),
+        Local(prefix, path) => {
+            policy.local
+                && match &policy.local_root {
+                    None => true,
+                    Some(dir) => match local_file_path(resolver, *prefix, path)
+                    {
+                        Some(path) => normalize_path(&path).starts_with(dir),
+                        None => false,
+                    },
+                }
+        }
+        Remote(_) => policy.remote,
+        Env(_) => policy.env,
+    };
+    let too_deep = match policy.max_depth {
+        Some(depth) => import_stack.len() >= depth,
+        None => false,
+    };
+    // Imports being resolved are not in the cache yet
+    let too_many = match policy.max_imports {
+        Some(count) => {
+            !import_cache.contains_key(import)
+                && import_cache.len() + import_stack.len() >= count
+        }
+        None => false,
+    };
+    if allowed && !too_deep && !too_many {
+        Ok(())
+    } else {
+        Err(ImportError::Forbidden(import.clone()))
+    }
+}
+
+fn do_resolve_expr(
+    parsed: Parsed,
+    resolver: &ImportResolver,
+    import_cache: &mut ImportCache,
+    import_stack: &ImportStack,
+) -> Result<Resolved, ImportError> {
+    let Parsed(mut expr, root) = parsed;
+    let mut resolve = |import: Import| -> Result<Normalized, ImportError> {
+        let chained = chain_import(&root, &import).map_err(|e| match e {
+            Error::Resolve(e) => e,
+            e => ImportError::Recursive(import.clone(), Box::new(e)),
+        })?;
+        if import_stack.contains(&chained) {
+            return Err(ImportError::ImportCycle(
+                import_stack.clone(),
+                chained,
+            ));
+        }
+        // Checked before any cache, see `ImportPolicy`
+        check_policy(resolver, &chained, import_cache, import_stack)?;
+        if let Some(expr) = import_cache.get(&chained) {
+            return Ok(expr.clone());
+        }
+        let hash = match &chained.hash {
+            Some(Hash::SHA256(hash)) => Some(hash.clone()),
+            None => None,
+        };
+
+        // Look for hashed imports in the semantic cache
+        if let (Some(cache), Some(hash)) = (&resolver.cache, &hash) {
+            if let Some(expr) = cache.get(hash) {
+                import_cache.insert(chained, expr.clone());
+                return Ok(expr);
+            }
+        }
+
+        // Copy the import stack and push the current import
+        let mut import_stack = import_stack.clone();
+        import_stack.push(chained.clone());
+
+        // Resolve the import recursively
+        let expr = resolve_import(
+            &import,
+            &chained,
+            &root,
+            resolver,
+            import_cache,
+            &import_stack,
+        )?;
+
+        // Check the integrity of the result and store it in the semantic cache
+        if let Some(expected) = hash {
+            let data = binary::encode(&expr.to_expr_alpha()).map_err(|e| {
+                ImportError::Recursive(import.clone(), Box::new(e.into()))
+            })?;
+            let actual = Sha256::digest(&data).to_vec();
+            if actual != expected {
+                return Err(ImportError::HashMismatch(
+                    import,
+                    Hash::SHA256(actual),
+                ));
+            }
+            if let Some(cache) = &resolver.cache {
+                cache.insert(&expected, &data);
+            }
+        }
+
+        // Add the import to the cache
+        import_cache.insert(chained, expr.clone());
+        Ok(expr)
+    };
+    traverse_resolve_expr(&mut expr, &mut resolve)?;
+    Ok(Resolved(expr))
+}
+
+pub(crate) fn resolve(e: Parsed) -> Result<Resolved, ImportError> {
+    resolve_with(e, &ImportResolver::new())
+}
+
+pub(crate) fn resolve_with(
+    e: Parsed,
+    resolver: &ImportResolver,
+) -> Result<Resolved, ImportError> {
+    let e = match &resolver.root_dir {
+        Some(dir) => Parsed(e.0, ImportRoot::LocalDir(dir.clone())),
+        None => e,
+    };
+    do_resolve_expr(e, resolver, &mut HashMap::new(), &Vec::new())
+}
+
+pub(crate) fn skip_resolve_expr(
+    parsed: Parsed,
+) -> Result<Resolved, ImportError> {
+    let mut expr = parsed.0;
+    let mut resolve = |import: Import| -> Result<Normalized, ImportError> {
+        Err(ImportError::UnexpectedImport(import))
+    };
+    traverse_resolve_expr(&mut expr, &mut resolve)?;
+    Ok(Resolved(expr))
+}
+
+pub trait Canonicalize {
+    fn canonicalize(&self) -> Self;
+}
+
+impl Canonicalize for FilePath {
+    fn canonicalize(&self) -> FilePath {
+        let mut file_path: Vec<String> = Vec::new();
+
+        for c in &self.file_path {
+            match c.as_str() {
+                // canonicalize(directory₀) = directory₁
+                // ───────────────────────────────────────
+                // canonicalize(directory₀/.) = directory₁
+                "." => {}
+
+                ".." => match file_path.last() {
+                    // canonicalize(directory₀) = directory₁/component
+                    // ───────────────────────────────────────────────  ; If "component" is not
+                    // canonicalize(directory₀/..) = directory₁         ; ".."
+                    Some(last) if last != ".." => {
+                        file_path.pop();
+                    }
+
+                    // canonicalize(directory₀) = ε
+                    // ────────────────────────────
+                    // canonicalize(directory₀/..) = /..
+                    //
+                    // canonicalize(directory₀) = directory₁/..
+                    // ──────────────────────────────────────────────
+                    // canonicalize(directory₀/..) = directory₁/../..
+                    _ => file_path.push("..".to_string()),
+                },
+
+                // canonicalize(directory₀) = directory₁
+                // ─────────────────────────────────────────────────────────  ; If no other
+                // canonicalize(directory₀/component) = directory₁/component  ; rule matches
+                _ => file_path.push(c.clone()),
+            }
+        }
+
+        FilePath { file_path }
+    }
+}
+
+impl<SE: Clone> Canonicalize for ImportLocation<SE> {
+    fn canonicalize(&self) -> ImportLocation<SE> {
+        match self {
+            ImportLocation::Local(prefix, file) => {
+                ImportLocation::Local(*prefix, file.canonicalize())
+            }
+            ImportLocation::Remote(url) => ImportLocation::Remote(URL {
+                scheme: url.scheme,
+                authority: url.authority.clone(),
+                path: url.path.canonicalize(),
+                query: url.query.clone(),
+                headers: url.headers.clone(),
+            }),
+            ImportLocation::Env(name) => ImportLocation::Env(name.to_string()),
+            ImportLocation::Missing => ImportLocation::Missing,
+        }
+    }
+}
@@ -2,14 +2,15 @@ use std::borrow::Cow;
 use std::cmp::max;
 use std::collections::HashMap;
 
-use crate::error::{ErrorBuilder, TypeError, TypeMessage};
+use crate::error::{TypeError, TypeMessage};
 use crate::semantics::merge_maps;
 use crate::semantics::{
     type_of_builtin, Binder, BuiltinClosure, Closure, TyEnv, TyExpr,
     TyExprKind, Type, Value, ValueKind,
 };
 use crate::syntax::{
-    BinOp, Builtin, Const, Expr, ExprKind, InterpolatedTextContents, Span,
+    BinOp, Builtin, Const, Expr, ExprKind, InterpolatedTextContents, Label,
+    Span,
 };
 use crate::Normalized;
 
@@ -17,19 +18,12 @@ fn type_of_recordtype<'a>(
     span: Span,
     tys: impl Iterator<Item = Cow<'a, TyExpr>>,
 ) -> Result<Type, TypeError> {
-    let span_err = |msg: &str| {
-        mkerr(
-            ErrorBuilder::new(msg.to_string())
-                .span_err(span.clone(), msg.to_string())
-                .format(),
-        )
-    };
     // An empty record type has type Type
     let mut k = Const::Type;
     for t in tys {
         match t.get_type()?.as_const() {
             Some(c) => k = max(k, c),
-            None => return span_err("InvalidFieldType"),
+            None => return mkerr(TypeMessage::InvalidFieldType { span }),
         }
     }
     Ok(Value::from_const(k))
@@ -43,8 +37,8 @@ fn function_check(a: Const, b: Const) -> Const {
     }
 }
 
-fn mkerr<T, S: ToString>(x: S) -> Result<T, TypeError> {
-    Err(TypeError::new(TypeMessage::Custom(x.to_string())))
+fn mkerr<T>(msg: TypeMessage) -> Result<T, TypeError> {
+    Err(TypeError::new(msg))
 }
 
 /// When all sub-expressions have been typed, check the remaining toplevel
@@ -54,14 +48,6 @@ fn type_one_layer(
     ekind: ExprKind<TyExpr, Normalized>,
     span: Span,
 ) -> Result<TyExpr, TypeError> {
-    let span_err = |msg: &str| {
-        mkerr(
-            ErrorBuilder::new(msg.to_string())
-                .span_err(span.clone(), msg.to_string())
-                .format(),
-        )
-    };
-
     let ty = match &ekind {
         ExprKind::Import(..) => unreachable!(
             "There should remain no imports in a resolved expression"
@@ -81,29 +67,20 @@ fn type_one_layer(
             let ks = match annot.get_type()?.as_const() {
                 Some(k) => k,
                 _ => {
-                    return mkerr(
-                        ErrorBuilder::new(format!(
-                            "Invalid input type: `{}`",
-                            annot.get_type()?.to_expr_tyenv(env),
-                        ))
-                        .span_err(
-                            annot.span(),
-                            format!(
-                                "this has type: `{}`",
-                                annot.get_type()?.to_expr_tyenv(env)
-                            ),
-                        )
-                        .help(format!(
-                            "The input type of a function must have type \
-                             `Type`, `Kind` or `Sort`",
-                        ))
-                        .format(),
-                    );
+                    return mkerr(TypeMessage::InvalidInputType {
+                        span: annot.span(),
+                        ty: annot.get_type()?.to_expr_tyenv(env),
+                    })
                 }
             };
             let kt = match body.get_type()?.as_const() {
                 Some(k) => k,
-                _ => return span_err("Invalid output type"),
+                _ => {
+                    return mkerr(TypeMessage::InvalidOutputType {
+                        span: body.span(),
+                        ty: body.get_type()?.to_expr_tyenv(env),
+                    })
+                }
             };
 
             Value::from_const(function_check(ks, kt))
@@ -121,13 +98,20 @@ fn type_one_layer(
         ExprKind::NaturalLit(_) => Value::from_builtin(Builtin::Natural),
         ExprKind::IntegerLit(_) => Value::from_builtin(Builtin::Integer),
         ExprKind::DoubleLit(_) => Value::from_builtin(Builtin::Double),
+        ExprKind::BytesLit(_) => Value::from_builtin(Builtin::Bytes),
+        ExprKind::DateLit(_) => Value::from_builtin(Builtin::Date),
+        ExprKind::TimeLit(_) => Value::from_builtin(Builtin::Time),
+        ExprKind::TimeZoneLit(_) => Value::from_builtin(Builtin::TimeZone),
         ExprKind::TextLit(interpolated) => {
             let text_type = Value::from_builtin(Builtin::Text);
             for contents in interpolated.iter() {
                 use InterpolatedTextContents::Expr;
                 if let Expr(x) = contents {
                     if x.get_type()? != text_type {
-                        return span_err("InvalidTextInterpolation");
+                        return mkerr(TypeMessage::InvalidTextInterpolation {
+                            span: x.span(),
+                            ty: x.get_type()?.to_expr_tyenv(env),
+                        });
                     }
                 }
             }
@@ -141,7 +125,12 @@ fn type_one_layer(
                     args,
                     ..
                 }) if args.len() == 1 => {}
-                _ => return span_err("InvalidListType"),
+                _ => {
+                    return mkerr(TypeMessage::InvalidListType {
+                        span,
+                        ty: t.to_expr_tyenv(env),
+                    })
+                }
             };
             t
         }
@@ -150,12 +139,21 @@ fn type_one_layer(
             let x = iter.next().unwrap();
             for y in iter {
                 if x.get_type()? != y.get_type()? {
-                    return span_err("InvalidListElement");
+                    return mkerr(TypeMessage::InvalidListElement {
+                        span: y.span(),
+                        expected: x.get_type()?.to_expr_tyenv(env),
+                        actual: y.get_type()?.to_expr_tyenv(env),
+                    });
                 }
             }
             let t = x.get_type()?;
             if t.get_type()?.as_const() != Some(Const::Type) {
-                return span_err("InvalidListType");
+                return mkerr(TypeMessage::InvalidListType {
+                    span,
+                    ty: Value::from_builtin(Builtin::List)
+                        .app(t)
+                        .to_expr_tyenv(env),
+                });
             }
 
             Value::from_builtin(Builtin::List).app(t)
@@ -163,7 +161,12 @@ fn type_one_layer(
         ExprKind::SomeLit(x) => {
             let t = x.get_type()?;
             if t.get_type()?.as_const() != Some(Const::Type) {
-                return span_err("InvalidOptionalType");
+                return mkerr(TypeMessage::InvalidOptionalType {
+                    span,
+                    ty: Value::from_builtin(Builtin::Optional)
+                        .app(t)
+                        .to_expr_tyenv(env),
+                });
             }
 
             Value::from_builtin(Builtin::Optional).app(t)
@@ -175,7 +178,10 @@ fn type_one_layer(
                 // Check for duplicated entries
                 match kts.entry(x.clone()) {
                     Entry::Occupied(_) => {
-                        return span_err("RecordTypeDuplicateField")
+                        return mkerr(TypeMessage::RecordTypeDuplicateField {
+                            span,
+                            label: x.clone(),
+                        })
                     }
                     Entry::Vacant(e) => e.insert(v.get_type()?),
                 };
@@ -195,7 +201,10 @@ fn type_one_layer(
                 // Check for duplicated entries
                 match seen_fields.entry(x.clone()) {
                     Entry::Occupied(_) => {
-                        return span_err("RecordTypeDuplicateField")
+                        return mkerr(TypeMessage::RecordTypeDuplicateField {
+                            span,
+                            label: x.clone(),
+                        })
                     }
                     Entry::Vacant(e) => e.insert(()),
                 };
@@ -216,12 +225,19 @@ fn type_one_layer(
                     match (k, t.get_type()?.as_const()) {
                         (None, Some(k2)) => k = Some(k2),
                         (Some(k1), Some(k2)) if k1 == k2 => {}
-                        _ => return span_err("InvalidFieldType"),
+                        _ => {
+                            return mkerr(TypeMessage::InvalidFieldType {
+                                span: t.span(),
+                            })
+                        }
                     }
                 }
                 match seen_fields.entry(x) {
                     Entry::Occupied(_) => {
-                        return span_err("UnionTypeDuplicateField")
+                        return mkerr(TypeMessage::UnionTypeDuplicateField {
+                            span,
+                            label: x.clone(),
+                        })
                     }
                     Entry::Vacant(e) => e.insert(()),
                 };
@@ -237,7 +253,13 @@ fn type_one_layer(
             match &*scrut.get_type()?.kind() {
                 ValueKind::RecordType(kts) => match kts.get(&x) {
                     Some(tth) => tth.clone(),
-                    None => return span_err("MissingRecordField"),
+                    None => {
+                        return mkerr(TypeMessage::MissingRecordField {
+                            span,
+                            label: x.clone(),
+                            record_type: scrut.get_type()?.to_expr_tyenv(env),
+                        })
+                    }
                 },
                 // TODO: branch here only when scrut.get_type() is a Const
                 _ => {
@@ -264,29 +286,34 @@ fn type_one_layer(
                                 )
                             }
                             Some(None) => scrut_nf,
-                            None => return span_err("MissingUnionField"),
+                            None => {
+                                return mkerr(TypeMessage::MissingUnionField {
+                                    span,
+                                    label: x.clone(),
+                                    union_type: scrut_nf.to_expr_tyenv(env),
+                                })
+                            }
                         },
-                        _ => return span_err("NotARecord"),
+                        _ => {
+                            return mkerr(TypeMessage::NotARecord {
+                                span: scrut.span(),
+                                label: x.clone(),
+                                ty: scrut.get_type()?.to_expr_tyenv(env),
+                            })
+                        }
                     }
-                } // _ => span_err("NotARecord"),
+                }
             }
         }
         ExprKind::Annot(x, t) => {
             let t = t.eval(env.as_nzenv());
             let x_ty = x.get_type()?;
             if x_ty != t {
-                return span_err(&format!(
-                    "annot mismatch: ({} : {}) : {}",
-                    x.to_expr_tyenv(env),
-                    x_ty.to_tyexpr(env.as_varenv()).to_expr_tyenv(env),
-                    t.to_tyexpr(env.as_varenv()).to_expr_tyenv(env)
-                ));
-                // return span_err(format!(
-                //     "annot mismatch: {} != {}",
-                //     x_ty.to_tyexpr(env.as_varenv()).to_expr_tyenv(env),
-                //     t.to_tyexpr(env.as_varenv()).to_expr_tyenv(env)
-                // ));
-                // return span_err(format!("annot mismatch: {:#?} : {:#?}", x, t,));
+                return mkerr(TypeMessage::AnnotMismatch {
+                    span,
+                    expected: t.to_expr_tyenv(env),
+                    actual: x_ty.to_expr_tyenv(env),
+                });
             }
             x_ty
         }
@@ -294,72 +321,67 @@ fn type_one_layer(
             let t = t.eval(env.as_nzenv());
             match &*t.kind() {
                 ValueKind::Equivalence(x, y) if x == y => {}
-                ValueKind::Equivalence(..) => {
-                    return span_err("AssertMismatch")
+                ValueKind::Equivalence(x, y) => {
+                    return mkerr(TypeMessage::AssertMismatch {
+                        span,
+                        lhs: x.to_expr_tyenv(env),
+                        rhs: y.to_expr_tyenv(env),
+                    })
+                }
+                _ => {
+                    return mkerr(TypeMessage::AssertMustTakeEquivalence {
+                        span,
+                        ty: t.to_expr_tyenv(env),
+                    })
                 }
-                _ => return span_err("AssertMustTakeEquivalence"),
             }
             t
         }
-        ExprKind::App(f, arg) => {
-            match f.get_type()?.kind() {
-                ValueKind::PiClosure { annot, closure, .. } => {
-                    if arg.get_type()? != *annot {
-                        return mkerr(
-                            ErrorBuilder::new(format!(
-                                "wrong type of function argument"
-                            ))
-                            .span_err(
-                                f.span(),
-                                format!(
-                                    "this expects an argument of type: {}",
-                                    annot.to_expr_tyenv(env),
-                                ),
-                            )
-                            .span_err(
-                                arg.span(),
-                                format!(
-                                    "but this has type: {}",
-                                    arg.get_type()?.to_expr_tyenv(env),
-                                ),
-                            )
-                            .note(format!(
-                                "expected type `{}`\n   found type `{}`",
-                                annot.to_expr_tyenv(env),
-                                arg.get_type()?.to_expr_tyenv(env),
-                            ))
-                            .format(),
-                        );
-                    }
+        ExprKind::App(f, arg) => match f.get_type()?.kind() {
+            ValueKind::PiClosure { annot, closure, .. } => {
+                if arg.get_type()? != *annot {
+                    return mkerr(TypeMessage::TypeMismatch {
+                        function: f.span(),
+                        argument: arg.span(),
+                        expected: annot.to_expr_tyenv(env),
+                        actual: arg.get_type()?.to_expr_tyenv(env),
+                    });
+                }
 
-                    let arg_nf = arg.eval(env.as_nzenv());
-                    closure.apply(arg_nf)
-                }
-                _ => return mkerr(
-                    ErrorBuilder::new(format!(
-                        "expected function, found `{}`",
-                        f.get_type()?.to_expr_tyenv(env)
-                    ))
-                    .span_err(
-                        f.span(),
-                        format!("function application requires a function",),
-                    )
-                    .format(),
-                ),
+                let arg_nf = arg.eval(env.as_nzenv());
+                closure.apply(arg_nf)
             }
-        }
+            _ => {
+                return mkerr(TypeMessage::NotAFunction {
+                    span: f.span(),
+                    ty: f.get_type()?.to_expr_tyenv(env),
+                })
+            }
+        },
         ExprKind::BoolIf(x, y, z) => {
             if *x.get_type()?.kind() != ValueKind::from_builtin(Builtin::Bool) {
-                return span_err("InvalidPredicate");
+                return mkerr(TypeMessage::InvalidPredicate {
+                    span: x.span(),
+                    ty: x.get_type()?.to_expr_tyenv(env),
+                });
             }
-            if y.get_type()?.get_type()?.as_const() != Some(Const::Type) {
-                return span_err("IfBranchMustBeTerm");
-            }
-            if z.get_type()?.get_type()?.as_const() != Some(Const::Type) {
-                return span_err("IfBranchMustBeTerm");
+            for branch in &[y, z] {
+                if branch.get_type()?.get_type()?.as_const()
+                    != Some(Const::Type)
+                {
+                    return mkerr(TypeMessage::IfBranchMustBeTerm {
+                        span: branch.span(),
+                        ty: branch.get_type()?.to_expr_tyenv(env),
+                    });
+                }
             }
             if y.get_type()? != z.get_type()? {
-                return span_err("IfBranchMismatch");
+                return mkerr(TypeMessage::IfBranchMismatch {
+                    then_span: y.span(),
+                    else_span: z.span(),
+                    then_type: y.get_type()?.to_expr_tyenv(env),
+                    else_type: z.get_type()?.to_expr_tyenv(env),
+                });
             }
 
             y.get_type()?
@@ -371,12 +393,22 @@ fn type_one_layer(
             // Extract the LHS record type
             let kts_x = match x_type.kind() {
                 ValueKind::RecordType(kts) => kts,
-                _ => return span_err("MustCombineRecord"),
+                _ => {
+                    return mkerr(TypeMessage::MustCombineRecord {
+                        span: x.span(),
+                        ty: x_type.to_expr_tyenv(env),
+                    })
+                }
             };
             // Extract the RHS record type
             let kts_y = match y_type.kind() {
                 ValueKind::RecordType(kts) => kts,
-                _ => return span_err("MustCombineRecord"),
+                _ => {
+                    return mkerr(TypeMessage::MustCombineRecord {
+                        span: y.span(),
+                        ty: y_type.to_expr_tyenv(env),
+                    })
+                }
             };
 
             // Union the two records, prefering
@@ -406,11 +438,25 @@ fn type_one_layer(
             let y_val = y.eval(env.as_nzenv());
             let kts_x = match x_val.kind() {
                 ValueKind::RecordType(kts) => kts,
-                _ => return span_err("RecordTypeMergeRequiresRecordType"),
+                _ => {
+                    return mkerr(
+                        TypeMessage::RecordTypeMergeRequiresRecordType {
+                            span: x.span(),
+                            ty: x_val.to_expr_tyenv(env),
+                        },
+                    )
+                }
             };
             let kts_y = match y_val.kind() {
                 ValueKind::RecordType(kts) => kts,
-                _ => return span_err("RecordTypeMergeRequiresRecordType"),
+                _ => {
+                    return mkerr(
+                        TypeMessage::RecordTypeMergeRequiresRecordType {
+                            span: y.span(),
+                            ty: y_val.to_expr_tyenv(env),
+                        },
+                    )
+                }
             };
             for (k, tx) in kts_x {
                 if let Some(ty) = kts_y.get(k) {
@@ -438,21 +484,39 @@ fn type_one_layer(
                     b: Builtin::List,
                     ..
                 }) => {}
-                _ => return span_err("BinOpTypeMismatch"),
+                _ => {
+                    return mkerr(TypeMessage::BinOpTypeMismatch {
+                        span: l.span(),
+                        op: BinOp::ListAppend,
+                        ty: l_ty.to_expr_tyenv(env),
+                    })
+                }
             }
 
             if l_ty != r.get_type()? {
-                return span_err("BinOpTypeMismatch");
+                return mkerr(TypeMessage::BinOpTypeMismatch {
+                    span: r.span(),
+                    op: BinOp::ListAppend,
+                    ty: r.get_type()?.to_expr_tyenv(env),
+                });
             }
 
             l_ty
         }
         ExprKind::BinOp(BinOp::Equivalence, l, r) => {
             if l.get_type()? != r.get_type()? {
-                return span_err("EquivalenceTypeMismatch");
+                return mkerr(TypeMessage::EquivalenceTypeMismatch {
+                    lhs_span: l.span(),
+                    rhs_span: r.span(),
+                    lhs_type: l.get_type()?.to_expr_tyenv(env),
+                    rhs_type: r.get_type()?.to_expr_tyenv(env),
+                });
             }
             if l.get_type()?.get_type()?.as_const() != Some(Const::Type) {
-                return span_err("EquivalenceArgumentsMustBeTerms");
+                return mkerr(TypeMessage::EquivalenceArgumentsMustBeTerms {
+                    span: l.span(),
+                    ty: l.get_type()?.to_expr_tyenv(env),
+                });
             }
 
             Value::from_const(Const::Type)
@@ -473,12 +537,14 @@ fn type_one_layer(
                 BinOp::ImportAlt => unreachable!("ImportAlt leftover in tck"),
             });
 
-            if l.get_type()? != t {
-                return span_err("BinOpTypeMismatch");
-            }
-
-            if r.get_type()? != t {
-                return span_err("BinOpTypeMismatch");
+            for operand in &[l, r] {
+                if operand.get_type()? != t {
+                    return mkerr(TypeMessage::BinOpTypeMismatch {
+                        span: operand.span(),
+                        op: *o,
+                        ty: operand.get_type()?.to_expr_tyenv(env),
+                    });
+                }
             }
 
             t
@@ -487,7 +553,12 @@ fn type_one_layer(
             let record_type = record.get_type()?;
             let handlers = match record_type.kind() {
                 ValueKind::RecordType(kts) => kts,
-                _ => return span_err("Merge1ArgMustBeRecord"),
+                _ => {
+                    return mkerr(TypeMessage::Merge1ArgMustBeRecord {
+                        span: record.span(),
+                        ty: record_type.to_expr_tyenv(env),
+                    })
+                }
             };
 
             let union_type = union.get_type()?;
@@ -504,7 +575,12 @@ fn type_one_layer(
                     kts.insert("Some".into(), Some(ty.clone()));
                     Cow::Owned(kts)
                 }
-                _ => return span_err("Merge2ArgMustBeUnionOrOptional"),
+                _ => {
+                    return mkerr(TypeMessage::Merge2ArgMustBeUnionOrOptional {
+                        span: union.span(),
+                        ty: union_type.to_expr_tyenv(env),
+                    })
+                }
             };
 
             let mut inferred_type = None;
@@ -515,94 +591,74 @@ fn type_one_layer(
                         ValueKind::PiClosure { closure, annot, .. } => {
                             if variant_type != annot {
                                 return mkerr(
-                                    ErrorBuilder::new(format!(
-                                        "Wrong handler input type"
-                                    ))
-                                    .span_err(
+                                    TypeMessage::MergeHandlerInputMismatch {
                                         span,
-                                        format!("in this merge expression",),
-                                    )
-                                    .span_err(
-                                        record.span(),
-                                        format!(
-                                            "the handler for `{}` expects a \
-                                             value of type: `{}`",
-                                            x,
-                                            annot.to_expr_tyenv(env)
-                                        ),
-                                    )
-                                    .span_err(
-                                        union.span(),
-                                        format!(
-                                            "but the corresponding variant \
-                                             has type: `{}`",
-                                            variant_type.to_expr_tyenv(env)
-                                        ),
-                                    )
-                                    .format(),
+                                        handlers: record.span(),
+                                        variants: union.span(),
+                                        label: x.clone(),
+                                        expected: annot.to_expr_tyenv(env),
+                                        actual: variant_type.to_expr_tyenv(env),
+                                    },
                                 );
                             }
 
                             match closure.remove_binder() {
                                 Ok(v) => v,
-                                Err(()) => {
-                                    return span_err(
-                                        "MergeReturnTypeIsDependent",
-                                    )
-                                }
+                                Err(()) => return mkerr(
+                                    TypeMessage::MergeReturnTypeIsDependent {
+                                        span,
+                                        label: x.clone(),
+                                    },
+                                ),
                             }
                         }
                         _ => {
                             return mkerr(
-                                ErrorBuilder::new(format!(
-                                    "merge handler is not a function"
-                                ))
-                                .span_err(
+                                TypeMessage::MergeHandlerNotAFunction {
                                     span,
-                                    format!("in this merge expression"),
-                                )
-                                .span_err(
-                                    record.span(),
-                                    format!(
-                                        "the handler for `{}` has type: `{}`",
-                                        x,
-                                        handler_type.to_expr_tyenv(env)
-                                    ),
-                                )
-                                .span_help(
-                                    union.span(),
-                                    format!(
-                                        "the corresponding variant has type: \
-                                         `{}`",
-                                        variant_type.to_expr_tyenv(env)
-                                    ),
-                                )
-                                .help(format!(
-                                    "a handler for this variant must be a \
-                                     function that takes an input of type: \
-                                     `{}`",
-                                    variant_type.to_expr_tyenv(env)
-                                ))
-                                .format(),
+                                    handlers: record.span(),
+                                    variants: union.span(),
+                                    label: x.clone(),
+                                    handler_type: handler_type
+                                        .to_expr_tyenv(env),
+                                    variant_type: variant_type
+                                        .to_expr_tyenv(env),
+                                },
                             )
                         }
                     },
                     // Union alternative without type
                     Some(None) => handler_type.clone(),
-                    None => return span_err("MergeHandlerMissingVariant"),
+                    None => {
+                        return mkerr(TypeMessage::MergeHandlerMissingVariant {
+                            span,
+                            label: x.clone(),
+                        })
+                    }
                 };
                 match &inferred_type {
                     None => inferred_type = Some(handler_return_type),
                     Some(t) => {
                         if t != &handler_return_type {
-                            return span_err("MergeHandlerTypeMismatch");
+                            return mkerr(
+                                TypeMessage::MergeHandlerTypeMismatch {
+                                    span,
+                                    label: x.clone(),
+                                    expected: t.to_expr_tyenv(env),
+                                    actual: handler_return_type
+                                        .to_expr_tyenv(env),
+                                },
+                            );
                         }
                     }
                 }
             }
             for x in variants.keys() {
                 if !handlers.contains_key(x) {
-                    return span_err("MergeVariantMissingHandler");
+                    return mkerr(TypeMessage::MergeVariantMissingHandler {
+                        span,
+                        label: x.clone(),
+                    });
                 }
             }
 
@@ -611,21 +667,33 @@ fn type_one_layer(
             match (inferred_type, type_annot) {
                 (Some(t1), Some(t2)) => {
                     if t1 != t2 {
-                        return span_err("MergeAnnotMismatch");
+                        return mkerr(TypeMessage::MergeAnnotMismatch {
+                            span,
+                            expected: t2.to_expr_tyenv(env),
+                            actual: t1.to_expr_tyenv(env),
+                        });
                     }
                     t1
                 }
                 (Some(t), None) => t,
                 (None, Some(t)) => t,
-                (None, None) => return span_err("MergeEmptyNeedsAnnotation"),
+                (None, None) => {
+                    return mkerr(TypeMessage::MergeEmptyNeedsAnnotation {
+                        span,
+                    })
+                }
             }
         }
+        ExprKind::ShowConstructor(x) => type_of_show_constructor(env, x)?,
         ExprKind::ToMap(record, annot) => {
             let record_t = record.get_type()?;
             let kts = match record_t.kind() {
                 ValueKind::RecordType(kts) => kts,
                 _ => {
-                    return span_err("The argument to `toMap` must be a record")
+                    return mkerr(TypeMessage::MustMapARecord {
+                        span: record.span(),
+                        ty: record_t.to_expr_tyenv(env),
+                    })
                 }
             };
 
@@ -633,51 +701,56 @@ fn type_one_layer(
                 let annot = if let Some(annot) = annot {
                     annot
                 } else {
-                    return span_err(
-                        "`toMap` applied to an empty record requires a type \
-                         annotation",
-                    );
+                    return mkerr(TypeMessage::MissingToMapType { span });
                 };
                 let annot_val = annot.eval(env.as_nzenv());
 
-                let err_msg = "The type of `toMap x` must be of the form \
-                               `List { mapKey : Text, mapValue : T }`";
+                let err = || {
+                    mkerr(TypeMessage::InvalidToMapType {
+                        span: annot.span(),
+                        ty: annot_val.to_expr_tyenv(env),
+                    })
+                };
                 let arg = match annot_val.kind() {
                     ValueKind::AppliedBuiltin(BuiltinClosure {
                         b: Builtin::List,
                         args,
                         ..
                     }) if args.len() == 1 => &args[0],
-                    _ => return span_err(err_msg),
+                    _ => return err(),
                 };
                 let kts = match arg.kind() {
                     ValueKind::RecordType(kts) => kts,
-                    _ => return span_err(err_msg),
+                    _ => return err(),
                 };
                 if kts.len() != 2 {
-                    return span_err(err_msg);
+                    return err();
                 }
                 match kts.get(&"mapKey".into()) {
                     Some(t) if *t == Value::from_builtin(Builtin::Text) => {}
-                    _ => return span_err(err_msg),
+                    _ => return err(),
                 }
                 match kts.get(&"mapValue".into()) {
                     Some(_) => {}
-                    None => return span_err(err_msg),
+                    None => return err(),
                 }
                 annot_val
             } else {
                 let entry_type = kts.iter().next().unwrap().1.clone();
                 if entry_type.get_type()?.as_const() != Some(Const::Type) {
-                    return span_err(
-                        "`toMap` only accepts records of type `Type`",
-                    );
+                    return mkerr(TypeMessage::InvalidToMapRecordKind {
+                        span: record.span(),
+                        ty: record_t.to_expr_tyenv(env),
+                    });
                 }
-                for (_, t) in kts.iter() {
+                for (l, t) in kts.iter() {
                     if *t != entry_type {
-                        return span_err(
-                            "Every field of the record must have the same type",
-                        );
+                        return mkerr(TypeMessage::HeterogenousRecordToMap {
+                            span: record.span(),
+                            label: l.clone(),
+                            expected: entry_type.to_expr_tyenv(env),
+                            actual: t.to_expr_tyenv(env),
+                        });
                     }
                 }
 
@@ -693,7 +766,11 @@ fn type_one_layer(
                 if let Some(annot) = annot {
                     let annot_val = annot.eval(env.as_nzenv());
                     if output_type != annot_val {
-                        return span_err("Annotation mismatch");
+                        return mkerr(TypeMessage::MapTypeMismatch {
+                            span: annot.span(),
+                            expected: annot_val.to_expr_tyenv(env),
+                            actual: output_type.to_expr_tyenv(env),
+                        });
                     }
                 }
                 output_type
@@ -703,18 +780,34 @@ fn type_one_layer(
             let record_type = record.get_type()?;
             let kts = match record_type.kind() {
                 ValueKind::RecordType(kts) => kts,
-                _ => return span_err("ProjectionMustBeRecord"),
+                _ => {
+                    return mkerr(TypeMessage::ProjectionMustBeRecord {
+                        span: record.span(),
+                        ty: record_type.to_expr_tyenv(env),
+                    })
+                }
             };
 
             let mut new_kts = HashMap::new();
             for l in labels {
                 match kts.get(l) {
-                    None => return span_err("ProjectionMissingEntry"),
+                    None => {
+                        return mkerr(TypeMessage::ProjectionMissingEntry {
+                            span,
+                            label: l.clone(),
+                            record_type: record_type.to_expr_tyenv(env),
+                        })
+                    }
                     Some(t) => {
                         use std::collections::hash_map::Entry;
                         match new_kts.entry(l.clone()) {
                             Entry::Occupied(_) => {
-                                return span_err("ProjectionDuplicateField")
+                                return mkerr(
+                                    TypeMessage::ProjectionDuplicateField {
+                                        span,
+                                        label: l.clone(),
+                                    },
+                                )
                             }
                             Entry::Vacant(e) => e.insert(t.clone()),
                         }
@@ -728,60 +821,193 @@ fn type_one_layer(
             )
         }
         ExprKind::ProjectionByExpr(record, selection) => {
-            let record_type = record.get_type()?;
-            let rec_kts = match record_type.kind() {
-                ValueKind::RecordType(kts) => kts,
-                _ => return span_err("ProjectionMustBeRecord"),
-            };
+            type_of_projection_by_expr(env, record, selection)?
+        }
+        ExprKind::With(record, labels, value) => {
+            return type_of_with(env, record, labels, value, &span).map(|ty| {
+                TyExpr::new(TyExprKind::Expr(ekind), Some(ty), span)
+            });
+        }
+        ExprKind::Completion(ty, compl) => {
+            let ty = type_of_completion(env, ty, compl, &span)?;
+            return Ok(TyExpr::new(TyExprKind::Expr(ekind), Some(ty), span));
+        }
+    };
 
-            let selection_val = selection.eval(env.as_nzenv());
-            let sel_kts = match selection_val.kind() {
-                ValueKind::RecordType(kts) => kts,
-                _ => return span_err("ProjectionByExprTakesRecordType"),
-            };
+    Ok(TyExpr::new(TyExprKind::Expr(ekind), Some(ty), span))
+}
 
-            for (l, sel_ty) in sel_kts {
-                match rec_kts.get(l) {
-                    Some(rec_ty) => {
-                        if rec_ty != sel_ty {
-                            return span_err("ProjectionWrongType");
-                        }
-                    }
-                    None => return span_err("ProjectionMissingEntry"),
-                }
-            }
+/// `e.(T)` has type `T` if `e` is a record whose fields include those of `T`, with the same types.
+fn type_of_projection_by_expr(
+    env: &TyEnv,
+    record: &TyExpr,
+    selection: &TyExpr,
+) -> Result<Type, TypeError> {
+    let record_type = record.get_type()?;
+    let rec_kts = match record_type.kind() {
+        ValueKind::RecordType(kts) => kts,
+        _ => {
+            return mkerr(TypeMessage::ProjectionMustBeRecord {
+                span: record.span(),
+                ty: record_type.to_expr_tyenv(env),
+            })
+        }
+    };
 
-            selection_val
+    let selection_val = selection.eval(env.as_nzenv());
+    let sel_kts = match selection_val.kind() {
+        ValueKind::RecordType(kts) => kts,
+        _ => {
+            return mkerr(TypeMessage::ProjectionByExprTakesRecordType {
+                span: selection.span(),
+                ty: selection_val.to_expr_tyenv(env),
+            })
         }
-        ExprKind::Completion(ty, compl) => {
-            let ty_field_default = type_one_layer(
-                env,
-                ExprKind::Field(ty.clone(), "default".into()),
-                span.clone(),
-            )?;
-            let ty_field_type = type_one_layer(
-                env,
-                ExprKind::Field(ty.clone(), "Type".into()),
-                span.clone(),
-            )?;
-            let merge = type_one_layer(
-                env,
-                ExprKind::BinOp(
-                    BinOp::RightBiasedRecordMerge,
-                    ty_field_default,
-                    compl.clone(),
-                ),
-                span.clone(),
-            )?;
-            return type_one_layer(
-                env,
-                ExprKind::Annot(merge, ty_field_type),
-                span.clone(),
+    };
+
+    let mut missing: Vec<Label> = sel_kts
+        .keys()
+        .filter(|l| !rec_kts.contains_key(l))
+        .cloned()
+        .collect();
+    if !missing.is_empty() {
+        missing.sort();
+        return mkerr(TypeMessage::ProjectionByExprMissingFields {
+            record: record.span(),
+            selection: selection.span(),
+            record_type: record_type.to_expr_tyenv(env),
+            missing,
+        });
+    }
+    for (l, sel_ty) in sel_kts {
+        if rec_kts[l] != *sel_ty {
+            return mkerr(TypeMessage::ProjectionByExprTypeMismatch {
+                span: selection.span(),
+                label: l.clone(),
+                expected: sel_ty.to_expr_tyenv(env),
+                actual: rec_kts[l].to_expr_tyenv(env),
+            });
+        }
+    }
+
+    Ok(selection_val)
+}
+
+/// `showConstructor x` has type `Text` if `x` is a union or an `Optional`.
+fn type_of_show_constructor(
+    env: &TyEnv,
+    x: &TyExpr,
+) -> Result<Type, TypeError> {
+    let x_type = x.get_type()?;
+    match x_type.kind() {
+        ValueKind::UnionType(_) => {}
+        ValueKind::AppliedBuiltin(BuiltinClosure {
+            b: Builtin::Optional,
+            args,
+            ..
+        }) if args.len() == 1 => {}
+        _ => {
+            return mkerr(TypeMessage::ShowConstructorNotOnUnion {
+                span: x.span(),
+                ty: x_type.to_expr_tyenv(env),
+            })
+        }
+    }
+    Ok(Value::from_builtin(Builtin::Text))
+}
+
+/// `T::r` has the type of `(T.default ⫽ r) : T.Type`.
+fn type_of_completion(
+    env: &TyEnv,
+    ty: &TyExpr,
+    compl: &TyExpr,
+    span: &Span,
+) -> Result<Type, TypeError> {
+    let ty_field_default = type_one_layer(
+        env,
+        ExprKind::Field(ty.clone(), "default".into()),
+        span.clone(),
+    )?;
+    let ty_field_type = type_one_layer(
+        env,
+        ExprKind::Field(ty.clone(), "Type".into()),
+        span.clone(),
+    )?;
+    let merge = type_one_layer(
+        env,
+        ExprKind::BinOp(
+            BinOp::RightBiasedRecordMerge,
+            ty_field_default,
+            compl.clone(),
+        ),
+        span.clone(),
+    )?;
+    type_one_layer(env, ExprKind::Annot(merge, ty_field_type), span.clone())?
+        .get_type()
+}
+
+/// The type of `e with k.ks = v`: the type of `e`, with the type of `k` replaced by the type of
+/// `e.k with ks = v`. If `e` has no field `k`, `e.k` is taken to be `{=}`.
+fn type_of_with(
+    env: &TyEnv,
+    record: &TyExpr,
+    labels: &[Label],
+    value: &TyExpr,
+    span: &Span,
+) -> Result<Type, TypeError> {
+    with_type(
+        env,
+        &record.get_type()?,
+        record.span(),
+        span,
+        labels,
+        value.get_type()?,
+    )
+}
+
+/// `record_span` points to the record being updated; `span` to the whole `with` expression, for
+/// errors about nested fields.
+fn with_type(
+    env: &TyEnv,
+    record_type: &Type,
+    record_span: Span,
+    span: &Span,
+    labels: &[Label],
+    value_type: Type,
+) -> Result<Type, TypeError> {
+    let (first, rest) = match labels.split_first() {
+        Some(split) => split,
+        None => return Ok(value_type),
+    };
+    let kts = match record_type.kind() {
+        ValueKind::RecordType(kts) => kts,
+        _ => {
+            return mkerr(TypeMessage::WithMustBeRecord {
+                span: record_span,
+                ty: record_type.to_expr_tyenv(env),
+            })
+        }
+    };
+    let field_type = match kts.get(first) {
+        Some(field_type) => {
+            with_type(env, field_type, span.clone(), span, rest, value_type)?
+        }
+        None => {
+            let empty = Value::from_kind_and_type(
+                ValueKind::RecordType(HashMap::new()),
+                Value::from_const(Const::Type),
             );
+            with_type(env, &empty, span.clone(), span, rest, value_type)?
         }
     };
-
-    Ok(TyExpr::new(TyExprKind::Expr(ekind), Some(ty), span))
+    let mut kts = kts.clone();
+    kts.insert(first.clone(), field_type);
+    let ty = type_of_recordtype(
+        span.clone(),
+        kts.iter()
+            .map(|(_, t)| Cow::Owned(t.to_tyexpr(env.as_varenv()))),
+    )?;
+    Ok(Value::from_kind_and_type(ValueKind::RecordType(kts), ty))
 }
 
 /// `type_with` typechecks an expressio in the provided environment.
@@ -793,11 +1019,10 @@ pub(crate) fn type_with(
         ExprKind::Var(var) => match env.lookup(&var) {
             Some((v, ty)) => (TyExprKind::Var(v), Some(ty)),
             None => {
-                return mkerr(
-                    ErrorBuilder::new(format!("unbound variable `{}`", var))
-                        .span_err(expr.span(), "not found in this scope")
-                        .format(),
-                )
+                return mkerr(TypeMessage::UnboundVariable {
+                    span: expr.span(),
+                    var: var.clone(),
+                })
             }
         },
         ExprKind::Const(Const::Sort) => {
@@ -10,6 +10,32 @@ pub type Double = NaiveDouble;
 #[derive(Debug, Copy, Clone)]
 pub struct NaiveDouble(f64);
 
+/// A calendar date, e.g. `2020-01-31`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Date {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+/// A time of day, e.g. `12:30:00.5`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Time {
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    /// The digits after the decimal point, if any. Their number is significant:
+    /// `12:00:00.50` and `12:00:00.5` are different expressions.
+    pub fraction: String,
+}
+
+/// An offset from UTC, e.g. `+01:00`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TimeZone {
+    /// The offset in minutes.
+    pub minutes: i16,
+}
+
 /// Constants for a pure type system
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Const {
@@ -66,6 +92,10 @@ pub enum Builtin {
     Integer,
     Double,
     Text,
+    Bytes,
+    Date,
+    Time,
+    TimeZone,
     List,
     Optional,
     OptionalNone,
@@ -92,6 +122,11 @@ pub enum Builtin {
     OptionalFold,
     OptionalBuild,
     TextShow,
+    TextReplace,
+    BytesShow,
+    DateShow,
+    TimeShow,
+    TimeZoneShow,
 }
 
 // Each node carries an annotation.
@@ -141,8 +176,16 @@ pub enum ExprKind<SubExpr, Embed> {
     IntegerLit(Integer),
     ///  `3.24`
     DoubleLit(Double),
+    ///  `2020-01-01`
+    DateLit(Date),
+    ///  `12:00:00`
+    TimeLit(Time),
+    ///  `+01:00`
+    TimeZoneLit(TimeZone),
     ///  `"Some ${interpolated} text"`
     TextLit(InterpolatedText<SubExpr>),
+    ///  `0x"deadbeef"`
+    BytesLit(Vec<u8>),
     ///  `[] : t`
     EmptyListLit(SubExpr),
     ///  `[x, y, z]`
@@ -159,6 +202,8 @@ pub enum ExprKind<SubExpr, Embed> {
     Merge(SubExpr, SubExpr, Option<SubExpr>),
     ///  `toMap x : t`
     ToMap(SubExpr, Option<SubExpr>),
+    ///  `showConstructor x`
+    ShowConstructor(SubExpr),
     ///  `e.x`
     Field(SubExpr, Label),
     ///  `e.{ x, y, z }`
@@ -167,6 +212,8 @@ pub enum ExprKind<SubExpr, Embed> {
     ProjectionByExpr(SubExpr, SubExpr),
     ///  `x::y`
     Completion(SubExpr, SubExpr),
+    ///  `x with a.b = y`
+    With(SubExpr, Vec<Label>, SubExpr),
     /// `./some/path`
     Import(Import<SubExpr>),
     /// Embeds the result of resolving an import
@@ -208,7 +255,7 @@ impl<SE, E> ExprKind<SE, E> {
         self.traverse_ref_maybe_binder(|_, e| visit_subexpr(e))
     }
 
-    fn traverse_mut<'a, Err>(
+    pub(crate) fn traverse_mut<'a, Err>(
         &'a mut self,
         visit_subexpr: impl FnMut(&'a mut SE) -> Result<(), Err>,
     ) -> Result<(), Err> {
@@ -258,6 +305,9 @@ impl<E> Expr<E> {
     pub fn as_ref(&self) -> &UnspannedExpr<E> {
         &self.kind
     }
+    pub(crate) fn as_mut(&mut self) -> &mut UnspannedExpr<E> {
+        &mut self.kind
+    }
     pub fn span(&self) -> Span {
         self.span.clone()
     }
@@ -281,43 +331,6 @@ impl<E> Expr<E> {
             span,
         }
     }
-
-    pub fn traverse_resolve_mut<Err, F1>(
-        &mut self,
-        f: &mut F1,
-    ) -> Result<(), Err>
-    where
-        E: Clone,
-        F1: FnMut(Import<Expr<E>>) -> Result<E, Err>,
-    {
-        match self.kind.as_mut() {
-            ExprKind::BinOp(BinOp::ImportAlt, l, r) => {
-                let garbage_expr = ExprKind::BoolLit(false);
-                let new_self = if l.traverse_resolve_mut(f).is_ok() {
-                    l
-                } else {
-                    r.traverse_resolve_mut(f)?;
-                    r
-                };
-                *self.kind =
-                    std::mem::replace(new_self.kind.as_mut(), garbage_expr);
-            }
-            _ => {
-                self.kind.traverse_mut(|e| e.traverse_resolve_mut(f))?;
-                if let ExprKind::Import(import) = self.kind.as_mut() {
-                    let garbage_import = Import {
-                        mode: ImportMode::Code,
-                        location: ImportLocation::Missing,
-                        hash: None,
-                    };
-                    // Move out of &mut import
-                    let import = std::mem::replace(import, garbage_import);
-                    *self.kind = ExprKind::Embed(f(import)?);
-                }
-            }
-        }
-        Ok(())
-    }
 }
 
 pub fn trivial_result<T>(x: Result<T, !>) -> T {
@@ -356,6 +369,63 @@ impl From<NaiveDouble> for f64 {
     }
 }
 
+impl Date {
+    /// Returns `None` if the date does not exist.
+    pub fn new(year: u16, month: u8, day: u8) -> Option<Self> {
+        let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        let days_in_month = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap => 29,
+            2 => 28,
+            _ => return None,
+        };
+        if year > 9999 || day == 0 || day > days_in_month {
+            return None;
+        }
+        Some(Date { year, month, day })
+    }
+}
+
+impl Time {
+    /// Returns `None` if the time is out of range or `fraction` is not made of decimal digits.
+    /// Leap seconds are not supported.
+    pub fn new(
+        hour: u8,
+        minute: u8,
+        second: u8,
+        fraction: impl Into<String>,
+    ) -> Option<Self> {
+        let fraction = fraction.into();
+        if hour > 23
+            || minute > 59
+            || second > 59
+            || !fraction.bytes().all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+        Some(Time {
+            hour,
+            minute,
+            second,
+            fraction,
+        })
+    }
+}
+
+impl TimeZone {
+    /// Returns `None` if the offset is out of range.
+    pub fn new(positive: bool, hours: u8, minutes: u8) -> Option<Self> {
+        if hours > 23 || minutes > 59 {
+            return None;
+        }
+        let offset = hours as i16 * 60 + minutes as i16;
+        Some(TimeZone {
+            minutes: if positive { offset } else { -offset },
+        })
+    }
+}
+
 impl From<Label> for V {
     fn from(x: Label) -> V {
         V(x, 0)
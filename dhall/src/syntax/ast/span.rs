@@ -1,9 +1,17 @@
 use std::rc::Rc;
 
+/// A source text, along with where it comes from.
+#[derive(Debug)]
+pub(crate) struct Source {
+    /// A description of where the text comes from, e.g. a file path or a URL.
+    origin: String,
+    text: String,
+}
+
 /// A location in the source text
 #[derive(Debug, Clone)]
 pub struct ParsedSpan {
-    input: Rc<str>,
+    source: Rc<Source>,
     /// # Safety
     ///
     /// Must be a valid character boundary index into `input`.
@@ -24,24 +32,60 @@ pub enum Span {
     Artificial,
 }
 
+impl Source {
+    pub(crate) fn new(
+        origin: impl Into<String>,
+        text: impl Into<String>,
+    ) -> Self {
+        Source {
+            origin: origin.into(),
+            text: text.into(),
+        }
+    }
+}
+
 impl ParsedSpan {
-    pub(crate) fn to_input(&self) -> String {
-        self.input.to_string()
+    pub(crate) fn origin(&self) -> &str {
+        &self.source.origin
     }
-    /// Convert to a char range for consumption by annotate_snippets.
-    /// This compensates for  https://github.com/rust-lang/annotate-snippets-rs/issues/24
-    pub(crate) fn as_char_range(&self) -> (usize, usize) {
-        (
-            char_idx_from_byte_idx(&self.input, self.start),
-            char_idx_from_byte_idx(&self.input, self.end),
-        )
+    /// Whether the two spans point into the same source text.
+    pub(crate) fn same_source(&self, other: &ParsedSpan) -> bool {
+        Rc::ptr_eq(&self.source, &other.source)
+    }
+
+    /// Extract the lines of the source text covered by the given spans, which must all come from
+    /// the same source. Returns the excerpt, the number of its first line, and the position of
+    /// each span in the excerpt as a char range for consumption by annotate_snippets.
+    pub(crate) fn excerpt(
+        spans: &[&ParsedSpan],
+    ) -> (String, usize, Vec<(usize, usize)>) {
+        let text = &spans[0].source.text;
+        let start = spans.iter().map(|sp| sp.start).min().unwrap();
+        let end = spans.iter().map(|sp| sp.end).max().unwrap();
+        let first_line = text[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let last_line = text[end..]
+            .find('\n')
+            .map(|i| end + i)
+            .unwrap_or_else(|| text.len());
+        let excerpt = &text[first_line..last_line];
+        let line_start = 1 + text[..first_line].matches('\n').count();
+        let ranges = spans
+            .iter()
+            .map(|sp| {
+                (
+                    char_idx_from_byte_idx(excerpt, sp.start - first_line),
+                    char_idx_from_byte_idx(excerpt, sp.end - first_line),
+                )
+            })
+            .collect();
+        (excerpt.to_string(), line_start, ranges)
     }
 }
 
 impl Span {
-    pub(crate) fn make(input: Rc<str>, sp: pest::Span) -> Self {
+    pub(crate) fn make(source: Rc<Source>, sp: pest::Span) -> Self {
         Span::Parsed(ParsedSpan {
-            input,
+            source,
             start: sp.start(),
             end: sp.end(),
         })
@@ -54,13 +98,11 @@ impl Span {
         use std::cmp::{max, min};
         use Span::*;
         match (self, other) {
-            (Parsed(x), Parsed(y)) if Rc::ptr_eq(&x.input, &y.input) => {
-                Parsed(ParsedSpan {
-                    input: x.input.clone(),
-                    start: min(x.start, y.start),
-                    end: max(x.end, y.end),
-                })
-            }
+            (Parsed(x), Parsed(y)) if x.same_source(y) => Parsed(ParsedSpan {
+                source: x.source.clone(),
+                start: min(x.start, y.start),
+                end: max(x.end, y.end),
+            }),
             _ => panic!(
                 "Tried to union incompatible spans: {:?} and {:?}",
                 self, other
@@ -87,7 +129,7 @@ impl Span {
             self::Span::Parsed(span) => span,
             _ => return format!("[unknown location] {}", message),
         };
-        let span = Span::new(&*span.input, span.start, span.end).unwrap();
+        let span = Span::new(&span.source.text, span.start, span.end).unwrap();
         let err: ErrorVariant<!> = ErrorVariant::CustomError { message };
         let err = Error::new_from_span(err, span);
         format!("{}", err)
@@ -96,14 +138,7 @@ impl Span {
 
 /// Convert a byte idx into a string into a char idx for consumption by annotate_snippets.
 fn char_idx_from_byte_idx(input: &str, idx: usize) -> usize {
-    let char_idx = input
-        .char_indices()
-        .enumerate()
-        .find(|(_, (i, _))| *i == idx)
-        .map(|(i, (_, _))| i)
-        // We should be able to unwrap() here, but somehow it panics on an example from
-        // serde_dhall/lib.rs...
-        .unwrap_or(0);
+    let char_idx = input[..idx].chars().count();
     // Unix-style newlines are counted as two chars (see
     // https://github.com/rust-lang/annotate-snippets-rs/issues/24).
     let nbr_newlines = input[..idx].chars().filter(|c| *c == '\n').count();
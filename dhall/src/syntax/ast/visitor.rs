@@ -139,6 +139,10 @@ where
         NaturalLit(n) => NaturalLit(*n),
         IntegerLit(n) => IntegerLit(*n),
         DoubleLit(n) => DoubleLit(*n),
+        BytesLit(b) => BytesLit(b.clone()),
+        DateLit(d) => DateLit(*d),
+        TimeLit(t) => TimeLit(t.clone()),
+        TimeZoneLit(z) => TimeZoneLit(*z),
         TextLit(t) => TextLit(t.traverse_ref(|e| v.visit_subexpr(e))?),
         BinOp(o, x, y) => BinOp(*o, v.visit_subexpr(x)?, v.visit_subexpr(y)?),
         BoolIf(b, t, f) => BoolIf(
@@ -160,6 +164,7 @@ where
         ToMap(x, t) => {
             ToMap(v.visit_subexpr(x)?, opt(t, |e| v.visit_subexpr(e))?)
         }
+        ShowConstructor(x) => ShowConstructor(v.visit_subexpr(x)?),
         Field(e, l) => Field(v.visit_subexpr(e)?, l.clone()),
         Projection(e, ls) => Projection(v.visit_subexpr(e)?, ls.clone()),
         ProjectionByExpr(e, x) => {
@@ -168,6 +173,9 @@ where
         Completion(e, x) => {
             Completion(v.visit_subexpr(e)?, v.visit_subexpr(x)?)
         }
+        With(e, ls, x) => {
+            With(v.visit_subexpr(e)?, ls.clone(), v.visit_subexpr(x)?)
+        }
         Assert(e) => Assert(v.visit_subexpr(e)?),
         Import(i) => Import(i.traverse_ref(|e| v.visit_subexpr(e))?),
         Embed(a) => Embed(v.visit_embed(a)?),
@@ -232,7 +240,8 @@ where
     use crate::syntax::ExprKind::*;
     match input {
         Var(_) | Const(_) | Builtin(_) | BoolLit(_) | NaturalLit(_)
-        | IntegerLit(_) | DoubleLit(_) => {}
+        | IntegerLit(_) | DoubleLit(_) | BytesLit(_) | DateLit(_)
+        | TimeLit(_) | TimeZoneLit(_) => {}
         Lam(l, t, e) => {
             v.visit_subexpr(t)?;
             v.visit_subexpr_under_binder(l, e)?;
@@ -279,6 +288,7 @@ where
             v.visit_subexpr(x)?;
             opt(&mut v, t)?;
         }
+        ShowConstructor(x) => v.visit_subexpr(x)?,
         Field(e, _) => v.visit_subexpr(e)?,
         Projection(e, _) => v.visit_subexpr(e)?,
         ProjectionByExpr(e, x) => {
@@ -289,6 +299,10 @@ where
             v.visit_subexpr(x)?;
             v.visit_subexpr(y)?;
         }
+        With(x, _, y) => {
+            v.visit_subexpr(x)?;
+            v.visit_subexpr(y)?;
+        }
         Assert(e) => v.visit_subexpr(e)?,
         Import(i) => i.traverse_mut(|e| v.visit_subexpr(e))?,
         Embed(a) => v.visit_embed(a)?,
@@ -1,13 +1,14 @@
 use itertools::Itertools;
-use serde_cbor::value::value as cbor;
+use serde_cbor::value as cbor;
+use std::convert::TryFrom;
 use std::iter::FromIterator;
 
 use crate::error::DecodeError;
 use crate::syntax;
 use crate::syntax::{
-    Expr, ExprKind, FilePath, FilePrefix, Hash, ImportLocation, ImportMode,
-    Integer, InterpolatedText, Label, Natural, Scheme, Span, UnspannedExpr,
-    URL, V,
+    Date, Expr, ExprKind, FilePath, FilePrefix, Hash, ImportLocation,
+    ImportMode, InterpolatedText, Label, Natural, Scheme, Span, Time, TimeZone,
+    UnspannedExpr, URL, V,
 };
 use crate::DecodedExpr;
 
@@ -27,8 +28,10 @@ fn cbor_value_to_dhall(data: &cbor::Value) -> Result<DecodedExpr, DecodeError> {
     use cbor::Value::*;
     use syntax::{BinOp, Builtin, Const};
     use ExprKind::*;
+    // Out-of-range values are mapped to a value that the temporal constructors reject.
+    let small = |n: &i128| u8::try_from(*n).unwrap_or(std::u8::MAX);
     Ok(rc(match data {
-        String(s) => match Builtin::parse(s) {
+        Text(s) => match Builtin::parse(s) {
             Some(b) => ExprKind::Builtin(b),
             None => match s.as_str() {
                 "True" => BoolLit(true),
@@ -39,11 +42,12 @@ fn cbor_value_to_dhall(data: &cbor::Value) -> Result<DecodedExpr, DecodeError> {
                 _ => Err(DecodeError::WrongFormatError("builtin".to_owned()))?,
             },
         },
-        U64(n) => Var(V(Label::from("_"), *n as usize)),
-        F64(x) => DoubleLit((*x).into()),
+        Integer(n) if *n >= 0 => Var(V(Label::from("_"), *n as usize)),
+        Float(x) => DoubleLit((*x).into()),
         Bool(b) => BoolLit(*b),
+        Bytes(b) => BytesLit(b.clone()),
         Array(vec) => match vec.as_slice() {
-            [String(l), U64(n)] => {
+            [Text(l), Integer(n)] if *n >= 0 => {
                 if l.as_str() == "_" {
                     Err(DecodeError::WrongFormatError(
                         "`_` variable was encoded incorrectly".to_owned(),
@@ -52,7 +56,7 @@ fn cbor_value_to_dhall(data: &cbor::Value) -> Result<DecodedExpr, DecodeError> {
                 let l = Label::from(l.as_str());
                 Var(V(l, *n as usize))
             }
-            [U64(0), f, args @ ..] => {
+            [Integer(0), f, args @ ..] => {
                 if args.is_empty() {
                     Err(DecodeError::WrongFormatError(
                         "Function application must have at least one argument"
@@ -66,12 +70,12 @@ fn cbor_value_to_dhall(data: &cbor::Value) -> Result<DecodedExpr, DecodeError> {
                 }
                 return Ok(f);
             }
-            [U64(1), x, y] => {
+            [Integer(1), x, y] => {
                 let x = cbor_value_to_dhall(&x)?;
                 let y = cbor_value_to_dhall(&y)?;
                 Lam(Label::from("_"), x, y)
             }
-            [U64(1), String(l), x, y] => {
+            [Integer(1), Text(l), x, y] => {
                 if l.as_str() == "_" {
                     Err(DecodeError::WrongFormatError(
                         "`_` variable was encoded incorrectly".to_owned(),
@@ -82,12 +86,12 @@ fn cbor_value_to_dhall(data: &cbor::Value) -> Result<DecodedExpr, DecodeError> {
                 let l = Label::from(l.as_str());
                 Lam(l, x, y)
             }
-            [U64(2), x, y] => {
+            [Integer(2), x, y] => {
                 let x = cbor_value_to_dhall(&x)?;
                 let y = cbor_value_to_dhall(&y)?;
                 Pi(Label::from("_"), x, y)
             }
-            [U64(2), String(l), x, y] => {
+            [Integer(2), Text(l), x, y] => {
                 if l.as_str() == "_" {
                     Err(DecodeError::WrongFormatError(
                         "`_` variable was encoded incorrectly".to_owned(),
@@ -98,12 +102,12 @@ fn cbor_value_to_dhall(data: &cbor::Value) -> Result<DecodedExpr, DecodeError> {
                 let l = Label::from(l.as_str());
                 Pi(l, x, y)
             }
-            [U64(3), U64(13), x, y] => {
+            [Integer(3), Integer(13), x, y] => {
                 let x = cbor_value_to_dhall(&x)?;
                 let y = cbor_value_to_dhall(&y)?;
                 Completion(x, y)
             }
-            [U64(3), U64(n), x, y] => {
+            [Integer(3), Integer(n), x, y] => {
                 let x = cbor_value_to_dhall(&x)?;
                 let y = cbor_value_to_dhall(&y)?;
                 use BinOp::*;
@@ -127,27 +131,27 @@ fn cbor_value_to_dhall(data: &cbor::Value) -> Result<DecodedExpr, DecodeError> {
                 };
                 BinOp(op, x, y)
             }
-            [U64(4), t] => {
+            [Integer(4), t] => {
                 let t = cbor_value_to_dhall(&t)?;
                 EmptyListLit(rc(App(rc(ExprKind::Builtin(Builtin::List)), t)))
             }
-            [U64(4), Null, rest @ ..] => {
+            [Integer(4), Null, rest @ ..] => {
                 let rest = rest
                     .iter()
                     .map(cbor_value_to_dhall)
                     .collect::<Result<Vec<_>, _>>()?;
                 NEListLit(rest)
             }
-            [U64(5), Null, x] => {
+            [Integer(5), Null, x] => {
                 let x = cbor_value_to_dhall(&x)?;
                 SomeLit(x)
             }
             // Old-style optional literals
-            [U64(5), t] => {
+            [Integer(5), t] => {
                 let t = cbor_value_to_dhall(&t)?;
                 App(rc(ExprKind::Builtin(Builtin::OptionalNone)), t)
             }
-            [U64(5), t, x] => {
+            [Integer(5), t, x] => {
                 let x = cbor_value_to_dhall(&x)?;
                 let t = cbor_value_to_dhall(&t)?;
                 Annot(
@@ -155,31 +159,31 @@ fn cbor_value_to_dhall(data: &cbor::Value) -> Result<DecodedExpr, DecodeError> {
                     rc(App(rc(ExprKind::Builtin(Builtin::Optional)), t)),
                 )
             }
-            [U64(6), x, y] => {
+            [Integer(6), x, y] => {
                 let x = cbor_value_to_dhall(&x)?;
                 let y = cbor_value_to_dhall(&y)?;
                 Merge(x, y, None)
             }
-            [U64(6), x, y, z] => {
+            [Integer(6), x, y, z] => {
                 let x = cbor_value_to_dhall(&x)?;
                 let y = cbor_value_to_dhall(&y)?;
                 let z = cbor_value_to_dhall(&z)?;
                 Merge(x, y, Some(z))
             }
-            [U64(7), Object(map)] => {
+            [Integer(7), Map(map)] => {
                 let map = cbor_map_to_dhall_map(map)?;
                 RecordType(map)
             }
-            [U64(8), Object(map)] => {
+            [Integer(8), Map(map)] => {
                 let map = cbor_map_to_dhall_map(map)?;
                 RecordLit(map)
             }
-            [U64(9), x, String(l)] => {
+            [Integer(9), x, Text(l)] => {
                 let x = cbor_value_to_dhall(&x)?;
                 let l = Label::from(l.as_str());
                 Field(x, l)
             }
-            [U64(10), x, Array(arr)] => {
+            [Integer(10), x, Array(arr)] => {
                 let x = cbor_value_to_dhall(&x)?;
                 if let [y] = arr.as_slice() {
                     let y = cbor_value_to_dhall(&y)?;
@@ -190,12 +194,12 @@ fn cbor_value_to_dhall(data: &cbor::Value) -> Result<DecodedExpr, DecodeError> {
                     ))?
                 }
             }
-            [U64(10), x, rest @ ..] => {
+            [Integer(10), x, rest @ ..] => {
                 let x = cbor_value_to_dhall(&x)?;
                 let labels = rest
                     .iter()
                     .map(|s| match s {
-                        String(s) => Ok(Label::from(s.as_str())),
+                        Text(s) => Ok(Label::from(s.as_str())),
                         _ => Err(DecodeError::WrongFormatError(
                             "projection".to_owned(),
                         )),
@@ -203,23 +207,44 @@ fn cbor_value_to_dhall(data: &cbor::Value) -> Result<DecodedExpr, DecodeError> {
                     .collect::<Result<_, _>>()?;
                 Projection(x, labels)
             }
-            [U64(11), Object(map)] => {
+            [Integer(11), Map(map)] => {
                 let map = cbor_map_to_dhall_opt_map(map)?;
                 UnionType(map)
             }
-            [U64(12), ..] => Err(DecodeError::WrongFormatError(
+            [Integer(12), ..] => Err(DecodeError::WrongFormatError(
                 "Union literals are not supported anymore".to_owned(),
             ))?,
-            [U64(14), x, y, z] => {
+            [Integer(14), x, y, z] => {
                 let x = cbor_value_to_dhall(&x)?;
                 let y = cbor_value_to_dhall(&y)?;
                 let z = cbor_value_to_dhall(&z)?;
                 BoolIf(x, y, z)
             }
-            [U64(15), U64(x)] => NaturalLit(*x as Natural),
-            [U64(16), U64(x)] => IntegerLit(*x as Integer),
-            [U64(16), I64(x)] => IntegerLit(*x as Integer),
-            [U64(18), String(first), rest @ ..] => {
+            [Integer(15), Integer(x)] if *x >= 0 => NaturalLit(*x as Natural),
+            [Integer(16), Integer(x)] => IntegerLit(*x as syntax::Integer),
+            [Integer(100), Integer(year), Integer(month), Integer(day)] => {
+                let date = Date::new(
+                    u16::try_from(*year).unwrap_or(std::u16::MAX),
+                    small(month),
+                    small(day),
+                )
+                .ok_or_else(|| {
+                    DecodeError::WrongFormatError("date".to_owned())
+                })?;
+                DateLit(date)
+            }
+            [Integer(101), Integer(hour), Integer(minute), Tag(4, seconds)] => {
+                TimeLit(cbor_to_time(small(hour), small(minute), seconds)?)
+            }
+            [Integer(102), Bool(positive), Integer(hours), Integer(minutes)] => {
+                let zone =
+                    TimeZone::new(*positive, small(hours), small(minutes))
+                        .ok_or_else(|| {
+                            DecodeError::WrongFormatError("timezone".to_owned())
+                        })?;
+                TimeZoneLit(zone)
+            }
+            [Integer(18), Text(first), rest @ ..] => {
                 TextLit(InterpolatedText::from((
                     first.clone(),
                     rest.iter()
@@ -227,7 +252,7 @@ fn cbor_value_to_dhall(data: &cbor::Value) -> Result<DecodedExpr, DecodeError> {
                         .map(|(x, y)| {
                             let x = cbor_value_to_dhall(&x)?;
                             let y = match y {
-                                String(s) => s.clone(),
+                                Text(s) => s.clone(),
                                 _ => Err(DecodeError::WrongFormatError(
                                     "text".to_owned(),
                                 ))?,
@@ -237,11 +262,11 @@ fn cbor_value_to_dhall(data: &cbor::Value) -> Result<DecodedExpr, DecodeError> {
                         .collect::<Result<_, _>>()?,
                 )))
             }
-            [U64(19), t] => {
+            [Integer(19), t] => {
                 let t = cbor_value_to_dhall(&t)?;
                 Assert(t)
             }
-            [U64(24), hash, U64(mode), U64(scheme), rest @ ..] => {
+            [Integer(24), hash, Integer(mode), Integer(scheme), rest @ ..] => {
                 let mode = match mode {
                     0 => ImportMode::Code,
                     1 => ImportMode::RawText,
@@ -284,22 +309,22 @@ fn cbor_value_to_dhall(data: &cbor::Value) -> Result<DecodedExpr, DecodeError> {
                             ))?,
                         };
                         let authority = match rest.next() {
-                            Some(String(s)) => s.to_owned(),
+                            Some(Text(s)) => s.to_owned(),
                             _ => Err(DecodeError::WrongFormatError(
                                 "import/remote/authority".to_owned(),
                             ))?,
                         };
                         let query = match rest.next_back() {
                             Some(Null) => None,
-                            Some(String(s)) => Some(s.to_owned()),
+                            Some(Text(s)) => Some(s.to_owned()),
                             _ => Err(DecodeError::WrongFormatError(
                                 "import/remote/query".to_owned(),
                             ))?,
                         };
                         let file_path = rest
-                            .map(|s| match s.as_string() {
-                                Some(s) => Ok(s.clone()),
-                                None => Err(DecodeError::WrongFormatError(
+                            .map(|s| match s {
+                                Text(s) => Ok(s.clone()),
+                                _ => Err(DecodeError::WrongFormatError(
                                     "import/remote/path".to_owned(),
                                 )),
                             })
@@ -324,9 +349,9 @@ fn cbor_value_to_dhall(data: &cbor::Value) -> Result<DecodedExpr, DecodeError> {
                             ))?,
                         };
                         let file_path = rest
-                            .map(|s| match s.as_string() {
-                                Some(s) => Ok(s.clone()),
-                                None => Err(DecodeError::WrongFormatError(
+                            .map(|s| match s {
+                                Text(s) => Ok(s.clone()),
+                                _ => Err(DecodeError::WrongFormatError(
                                     "import/local/path".to_owned(),
                                 )),
                             })
@@ -336,7 +361,7 @@ fn cbor_value_to_dhall(data: &cbor::Value) -> Result<DecodedExpr, DecodeError> {
                     }
                     6 => {
                         let env = match rest.next() {
-                            Some(String(s)) => s.to_owned(),
+                            Some(Text(s)) => s.to_owned(),
                             _ => Err(DecodeError::WrongFormatError(
                                 "import/env".to_owned(),
                             ))?,
@@ -354,15 +379,18 @@ fn cbor_value_to_dhall(data: &cbor::Value) -> Result<DecodedExpr, DecodeError> {
                     location,
                 })
             }
-            [U64(25), bindings @ ..] => {
+            [Integer(25), bindings @ ..] => {
                 let mut tuples = bindings.iter().tuples();
                 let bindings = (&mut tuples)
                     .map(|(x, t, v)| {
-                        let x = x.as_string().ok_or_else(|| {
-                            DecodeError::WrongFormatError(
-                                "let/label".to_owned(),
-                            )
-                        })?;
+                        let x = match x {
+                            Text(x) => x,
+                            _ => {
+                                return Err(DecodeError::WrongFormatError(
+                                    "let/label".to_owned(),
+                                ))
+                            }
+                        };
                         let x = Label::from(x.as_str());
                         let t = match t {
                             Null => None,
@@ -381,24 +409,47 @@ fn cbor_value_to_dhall(data: &cbor::Value) -> Result<DecodedExpr, DecodeError> {
                     .rev()
                     .fold(expr, |acc, (x, t, v)| rc(Let(x, t, v, acc))));
             }
-            [U64(26), x, y] => {
+            [Integer(26), x, y] => {
                 let x = cbor_value_to_dhall(&x)?;
                 let y = cbor_value_to_dhall(&y)?;
                 Annot(x, y)
             }
-            [U64(27), x] => {
+            [Integer(27), x] => {
                 let x = cbor_value_to_dhall(&x)?;
                 ToMap(x, None)
             }
-            [U64(27), x, y] => {
+            [Integer(27), x, y] => {
                 let x = cbor_value_to_dhall(&x)?;
                 let y = cbor_value_to_dhall(&y)?;
                 ToMap(x, Some(y))
             }
-            [U64(28), x] => {
+            [Integer(28), x] => {
                 let x = cbor_value_to_dhall(&x)?;
                 EmptyListLit(x)
             }
+            [Integer(29), x, Array(labels), y] => {
+                let x = cbor_value_to_dhall(&x)?;
+                let labels = labels
+                    .iter()
+                    .map(|s| match s {
+                        Text(s) => Ok(Label::from(s.as_str())),
+                        _ => Err(DecodeError::WrongFormatError(
+                            "with".to_owned(),
+                        )),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                if labels.is_empty() {
+                    return Err(DecodeError::WrongFormatError(
+                        "with".to_owned(),
+                    ));
+                }
+                let y = cbor_value_to_dhall(&y)?;
+                With(x, labels, y)
+            }
+            [Integer(34), x] => {
+                let x = cbor_value_to_dhall(&x)?;
+                ShowConstructor(x)
+            }
             _ => Err(DecodeError::WrongFormatError(format!("{:?}", data)))?,
         },
         _ => Err(DecodeError::WrongFormatError(format!("{:?}", data)))?,
@@ -406,16 +457,21 @@ fn cbor_value_to_dhall(data: &cbor::Value) -> Result<DecodedExpr, DecodeError> {
 }
 
 fn cbor_map_to_dhall_map<'a, T>(
-    map: impl IntoIterator<Item = (&'a cbor::ObjectKey, &'a cbor::Value)>,
+    map: impl IntoIterator<Item = (&'a cbor::Value, &'a cbor::Value)>,
 ) -> Result<T, DecodeError>
 where
     T: FromIterator<(Label, DecodedExpr)>,
 {
     map.into_iter()
         .map(|(k, v)| -> Result<(_, _), _> {
-            let k = k.as_string().ok_or_else(|| {
-                DecodeError::WrongFormatError("map/key".to_owned())
-            })?;
+            let k = match k {
+                cbor::Value::Text(k) => k,
+                _ => {
+                    return Err(DecodeError::WrongFormatError(
+                        "map/key".to_owned(),
+                    ))
+                }
+            };
             let v = cbor_value_to_dhall(v)?;
             Ok((Label::from(k.as_ref()), v))
         })
@@ -423,16 +479,21 @@ where
 }
 
 fn cbor_map_to_dhall_opt_map<'a, T>(
-    map: impl IntoIterator<Item = (&'a cbor::ObjectKey, &'a cbor::Value)>,
+    map: impl IntoIterator<Item = (&'a cbor::Value, &'a cbor::Value)>,
 ) -> Result<T, DecodeError>
 where
     T: FromIterator<(Label, Option<DecodedExpr>)>,
 {
     map.into_iter()
         .map(|(k, v)| -> Result<(_, _), _> {
-            let k = k.as_string().ok_or_else(|| {
-                DecodeError::WrongFormatError("map/key".to_owned())
-            })?;
+            let k = match k {
+                cbor::Value::Text(k) => k,
+                _ => {
+                    return Err(DecodeError::WrongFormatError(
+                        "map/key".to_owned(),
+                    ))
+                }
+            };
             let v = match v {
                 cbor::Value::Null => None,
                 _ => Some(cbor_value_to_dhall(v)?),
@@ -441,3 +502,62 @@ where
         })
         .collect::<Result<_, _>>()
 }
+
+/// Seconds are encoded as a decimal fraction `mantissa * 10^exponent`. The mantissa is a bignum
+/// if it doesn't fit in 64 bits.
+fn cbor_to_time(
+    hour: u8,
+    minute: u8,
+    seconds: &cbor::Value,
+) -> Result<Time, DecodeError> {
+    use cbor::Value::{Array, Bytes, Integer, Tag};
+    let err =
+        |msg: &str| DecodeError::WrongFormatError(format!("time: {}", msg));
+    let (exponent, mantissa) = match seconds {
+        Array(vec) => match vec.as_slice() {
+            [Integer(exponent), Integer(mantissa)]
+                if *exponent <= 0 && *mantissa >= 0 =>
+            {
+                (*exponent, mantissa.to_string())
+            }
+            [Integer(exponent), Tag(2, bytes)] if *exponent <= 0 => {
+                match bytes.as_ref() {
+                    Bytes(bytes) => (*exponent, bytes_to_decimal(bytes)),
+                    _ => return Err(err("invalid seconds")),
+                }
+            }
+            _ => return Err(err("invalid seconds")),
+        },
+        _ => return Err(err("invalid seconds")),
+    };
+    let precision =
+        usize::try_from(-exponent).map_err(|_| err("invalid seconds"))?;
+    // Pad with zeros so that there is at least one digit before the decimal point
+    let padding = (precision + 1).saturating_sub(mantissa.len());
+    let digits = format!("{}{}", "0".repeat(padding), mantissa);
+    let (second, fraction) = digits.split_at(digits.len() - precision);
+    let second = second.parse().unwrap_or(std::u8::MAX);
+    Time::new(hour, minute, second, fraction).ok_or_else(|| err("out of range"))
+}
+
+/// The decimal digits of a big-endian unsigned integer.
+fn bytes_to_decimal(bytes: &[u8]) -> String {
+    // Decimal digits, from the least significant one
+    let mut digits: Vec<u8> = Vec::new();
+    for byte in bytes {
+        let mut carry = u32::from(*byte);
+        for digit in digits.iter_mut() {
+            let x = u32::from(*digit) * 256 + carry;
+            *digit = (x % 10) as u8;
+            carry = x / 10;
+        }
+        while carry > 0 {
+            digits.push((carry % 10) as u8);
+            carry /= 10;
+        }
+    }
+    if digits.is_empty() {
+        return "0".to_owned();
+    }
+    digits.iter().rev().map(|d| char::from(b'0' + d)).collect()
+}
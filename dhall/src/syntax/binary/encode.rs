@@ -1,4 +1,4 @@
-use serde_cbor::value::value as cbor;
+use serde_cbor::value as cbor;
 use std::vec;
 
 use crate::error::EncodeError;
@@ -43,7 +43,7 @@ fn serialize_subexpr<S, E>(ser: S, e: &Expr<E>) -> Result<S::Ok, S::Error>
 where
     S: serde::ser::Serializer,
 {
-    use cbor::Value::{String, I64, U64};
+    use cbor::Value::{Integer, Text};
     use std::iter::once;
     use syntax::Builtin;
     use syntax::ExprKind::*;
@@ -54,23 +54,57 @@ where
     }
     let cbor =
         |v: cbor::Value| -> self::Serialize<'_, E> { self::Serialize::CBOR(v) };
-    let tag = |x: u64| cbor(U64(x));
+    let tag = |x: u64| cbor(Integer(x.into()));
     let null = || cbor(cbor::Value::Null);
-    let label = |l: &Label| cbor(cbor::Value::String(l.into()));
+    let label = |l: &Label| cbor(Text(l.into()));
 
     match e.as_ref() {
         Const(c) => ser.serialize_str(&c.to_string()),
         Builtin(b) => ser.serialize_str(&b.to_string()),
         BoolLit(b) => ser.serialize_bool(*b),
-        NaturalLit(n) => ser_seq!(ser; tag(15), U64(*n as u64)),
-        IntegerLit(n) => ser_seq!(ser; tag(16), I64(*n as i64)),
+        NaturalLit(n) => ser_seq!(ser; tag(15), Integer(*n as i128)),
+        IntegerLit(n) => ser_seq!(ser; tag(16), Integer(*n as i128)),
         DoubleLit(n) => {
             let n: f64 = (*n).into();
             ser.serialize_f64(n)
         }
+        BytesLit(b) => ser.serialize_bytes(b),
+        DateLit(d) => ser_seq!(
+            ser;
+            tag(100),
+            Integer(d.year.into()),
+            Integer(d.month.into()),
+            Integer(d.day.into())
+        ),
+        TimeLit(t) => {
+            // Seconds are encoded as a decimal fraction `mantissa * 10^exponent`
+            let exponent = -(t.fraction.len() as i128);
+            let mantissa = format!("{}{}", t.second, t.fraction);
+            let seconds = cbor::Value::Tag(
+                4,
+                Box::new(cbor::Value::Array(vec![
+                    Integer(exponent),
+                    decimal_to_cbor(&mantissa),
+                ])),
+            );
+            ser_seq!(
+                ser;
+                tag(101),
+                Integer(t.hour.into()),
+                Integer(t.minute.into()),
+                seconds
+            )
+        }
+        TimeZoneLit(z) => ser_seq!(
+            ser;
+            tag(102),
+            cbor::Value::Bool(z.minutes >= 0),
+            Integer((z.minutes.abs() / 60).into()),
+            Integer((z.minutes.abs() % 60).into())
+        ),
         BoolIf(x, y, z) => ser_seq!(ser; tag(14), expr(x), expr(y), expr(z)),
         Var(V(l, n)) if l == &"_".into() => ser.serialize_u64(*n as u64),
-        Var(V(l, n)) => ser_seq!(ser; label(l), U64(*n as u64)),
+        Var(V(l, n)) => ser_seq!(ser; label(l), Integer(*n as i128)),
         Lam(l, x, y) if l == &"_".into() => {
             ser_seq!(ser; tag(1), expr(x), expr(y))
         }
@@ -121,10 +155,10 @@ where
             once(tag(4)).chain(once(null())).chain(xs.iter().map(expr)),
         ),
         TextLit(xs) => {
-            use syntax::InterpolatedTextContents::{Expr, Text};
+            use syntax::InterpolatedTextContents;
             ser.collect_seq(once(tag(18)).chain(xs.iter().map(|x| match x {
-                Expr(x) => expr(x),
-                Text(x) => cbor(String(x.clone())),
+                InterpolatedTextContents::Expr(x) => expr(x),
+                InterpolatedTextContents::Text(x) => cbor(Text(x.clone())),
             })))
         }
         RecordType(map) => ser_seq!(ser; tag(7), RecordMap(map)),
@@ -148,7 +182,7 @@ where
                 ImportAlt => 11,
                 Equivalence => 12,
             };
-            ser_seq!(ser; tag(3), U64(op), expr(x), expr(y))
+            ser_seq!(ser; tag(3), Integer(op), expr(x), expr(y))
         }
         Merge(x, y, None) => ser_seq!(ser; tag(6), expr(x), expr(y)),
         Merge(x, y, Some(z)) => {
@@ -156,6 +190,7 @@ where
         }
         ToMap(x, None) => ser_seq!(ser; tag(27), expr(x)),
         ToMap(x, Some(y)) => ser_seq!(ser; tag(27), expr(x), expr(y)),
+        ShowConstructor(x) => ser_seq!(ser; tag(34), expr(x)),
         Projection(x, ls) => ser.collect_seq(
             once(tag(10))
                 .chain(once(expr(x)))
@@ -165,6 +200,13 @@ where
             ser_seq!(ser; tag(10), expr(x), vec![expr(y)])
         }
         Completion(x, y) => ser_seq!(ser; tag(3), tag(13), expr(x), expr(y)),
+        With(x, ls, y) => ser_seq!(
+            ser;
+            tag(29),
+            expr(x),
+            ls.iter().map(label).collect::<Vec<_>>(),
+            expr(y)
+        ),
         Import(import) => serialize_import(ser, import),
         Embed(_) => unimplemented!(
             "An expression with resolved imports cannot be binary-encoded"
@@ -179,7 +221,7 @@ fn serialize_import<S, E>(
 where
     S: serde::ser::Serializer,
 {
-    use cbor::Value::{Bytes, Null, U64};
+    use cbor::Value::{Bytes, Integer, Null};
     use serde::ser::SerializeSeq;
 
     let count = 4 + match &import.location {
@@ -190,7 +232,7 @@ where
     };
     let mut ser_seq = ser.serialize_seq(Some(count))?;
 
-    ser_seq.serialize_element(&U64(24))?;
+    ser_seq.serialize_element(&Integer(24))?;
 
     let hash = match &import.hash {
         None => Null,
@@ -207,7 +249,7 @@ where
         ImportMode::RawText => 1,
         ImportMode::Location => 2,
     };
-    ser_seq.serialize_element(&U64(mode))?;
+    ser_seq.serialize_element(&Integer(mode))?;
 
     let scheme = match &import.location {
         ImportLocation::Remote(url) => match url.scheme {
@@ -223,7 +265,7 @@ where
         ImportLocation::Env(_) => 6,
         ImportLocation::Missing => 7,
     };
-    ser_seq.serialize_element(&U64(scheme))?;
+    ser_seq.serialize_element(&Integer(scheme))?;
 
     match &import.location {
         ImportLocation::Remote(url) => {
@@ -266,7 +308,7 @@ impl<'a, E> serde::ser::Serialize for Serialize<'a, E> {
             Serialize::CBOR(v) => v.serialize(ser),
             Serialize::RecordMap(map) => {
                 ser.collect_map(map.iter().map(|(k, v)| {
-                    (cbor::Value::String(k.into()), Serialize::Expr(v))
+                    (cbor::Value::Text(k.into()), Serialize::Expr(v))
                 }))
             }
             Serialize::UnionMap(map) => {
@@ -275,7 +317,7 @@ impl<'a, E> serde::ser::Serialize for Serialize<'a, E> {
                         Some(x) => Serialize::Expr(x),
                         None => Serialize::CBOR(cbor::Value::Null),
                     };
-                    (cbor::Value::String(k.into()), v)
+                    (cbor::Value::Text(k.into()), v)
                 }))
             }
         }
@@ -320,3 +362,26 @@ fn collect_nested_lets<'a, E>(
     let e = go(e, &mut vec);
     (e, vec)
 }
+
+/// A non-negative decimal integer, as a bignum if it doesn't fit in 64 bits.
+fn decimal_to_cbor(digits: &str) -> cbor::Value {
+    if let Ok(n) = digits.parse::<u64>() {
+        return cbor::Value::Integer(n.into());
+    }
+    // Big-endian bytes, built from the least significant byte
+    let mut bytes: Vec<u8> = Vec::new();
+    for digit in digits.bytes() {
+        let mut carry = u32::from(digit - b'0');
+        for byte in bytes.iter_mut() {
+            let x = u32::from(*byte) * 10 + carry;
+            *byte = x as u8;
+            carry = x >> 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    bytes.reverse();
+    cbor::Value::Tag(2, Box::new(cbor::Value::Bytes(bytes)))
+}
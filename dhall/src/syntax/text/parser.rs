@@ -9,9 +9,9 @@ use crate::syntax;
 use crate::syntax::map::{DupTreeMap, DupTreeSet};
 use crate::syntax::ExprKind::*;
 use crate::syntax::{
-    Double, FilePath, FilePrefix, Hash, ImportLocation, ImportMode, Integer,
-    InterpolatedText, InterpolatedTextContents, Label, NaiveDouble, Natural,
-    Scheme, Span, URL, V,
+    Date, Double, FilePath, FilePrefix, Hash, ImportLocation, ImportMode,
+    Integer, InterpolatedText, InterpolatedTextContents, Label, NaiveDouble,
+    Natural, Scheme, Source, Span, Time, TimeZone, URL, V,
 };
 use crate::Normalized;
 
@@ -24,7 +24,7 @@ type Expr = syntax::Expr<Normalized>;
 type UnspannedExpr = syntax::UnspannedExpr<Normalized>;
 type ParsedText = InterpolatedText<Expr>;
 type ParsedTextContents = InterpolatedTextContents<Expr>;
-type ParseInput<'input> = pest_consume::Node<'input, Rule, Rc<str>>;
+type ParseInput<'input> = pest_consume::Node<'input, Rule, Rc<Source>>;
 
 pub type ParseError = pest::error::Error<Rule>;
 pub type ParseResult<T> = Result<T, ParseError>;
@@ -45,6 +45,10 @@ impl crate::syntax::Builtin {
             "Integer" => Some(Integer),
             "Double" => Some(Double),
             "Text" => Some(Text),
+            "Bytes" => Some(Bytes),
+            "Date" => Some(Date),
+            "Time" => Some(Time),
+            "TimeZone" => Some(TimeZone),
             "List" => Some(List),
             "Optional" => Some(Optional),
             "None" => Some(OptionalNone),
@@ -71,6 +75,11 @@ impl crate::syntax::Builtin {
             "Optional/fold" => Some(OptionalFold),
             "Optional/build" => Some(OptionalBuild),
             "Text/show" => Some(TextShow),
+            "Text/replace" => Some(TextReplace),
+            "Bytes/show" => Some(BytesShow),
+            "Date/show" => Some(DateShow),
+            "Time/show" => Some(TimeShow),
+            "TimeZone/show" => Some(TimeZoneShow),
             _ => None,
         }
     }
@@ -127,6 +136,29 @@ fn trim_indent(lines: &mut Vec<ParsedText>) {
     }
 }
 
+fn binop_expression(l: Expr, op: ParseInput, r: Expr) -> ParseResult<Expr> {
+    use crate::syntax::BinOp::*;
+    use Rule::*;
+    let op = match op.as_rule() {
+        import_alt => ImportAlt,
+        bool_or => BoolOr,
+        natural_plus => NaturalPlus,
+        text_append => TextAppend,
+        list_append => ListAppend,
+        bool_and => BoolAnd,
+        combine => RecursiveRecordMerge,
+        prefer => RightBiasedRecordMerge,
+        combine_types => RecursiveRecordTypeMerge,
+        natural_times => NaturalTimes,
+        bool_eq => BoolEQ,
+        bool_ne => BoolNE,
+        equivalent => Equivalence,
+        r => Err(op.error(format!("Rule {:?} isn't an operator", r)))?,
+    };
+
+    Ok(spanned_union(l.span(), r.span(), BinOp(op, l, r)))
+}
+
 lazy_static::lazy_static! {
     static ref PRECCLIMBER: PrecClimber<Rule> = {
         use Rule::*;
@@ -411,6 +443,75 @@ impl DhallParser {
         }
     }
 
+    fn bytes_literal(input: ParseInput) -> ParseResult<Vec<u8>> {
+        let s = input.as_str();
+        // Strip `0x"` and `"`
+        Ok(hex::decode(&s[3..s.len() - 1]).unwrap())
+    }
+
+    fn temporal_literal(input: ParseInput) -> ParseResult<Expr> {
+        // Combined literals are sugar for records
+        let record = |fields: Vec<(&str, Expr)>| {
+            RecordLit(fields.into_iter().map(|(k, v)| (k.into(), v)).collect())
+        };
+        Ok(match_nodes!(input.children();
+            [full_date(d)] => d,
+            [partial_time(t)] => t,
+            [time_numoffset(z)] => z,
+            [full_date(d), partial_time(t)] => {
+                spanned(input, record(vec![("date", d), ("time", t)]))
+            },
+            [partial_time(t), time_numoffset(z)] => {
+                spanned(input, record(vec![("time", t), ("timeZone", z)]))
+            },
+            [full_date(d), partial_time(t), time_numoffset(z)] => {
+                spanned(
+                    input,
+                    record(vec![("date", d), ("time", t), ("timeZone", z)]),
+                )
+            },
+        ))
+    }
+
+    fn full_date(input: ParseInput) -> ParseResult<Expr> {
+        let s = input.as_str();
+        let date = Date::new(
+            s[0..4].parse().unwrap(),
+            s[5..7].parse().unwrap(),
+            s[8..10].parse().unwrap(),
+        )
+        .ok_or_else(|| input.error(format!("Invalid date: '{}'", s)))?;
+        Ok(spanned(input, DateLit(date)))
+    }
+
+    fn partial_time(input: ParseInput) -> ParseResult<Expr> {
+        let s = input.as_str();
+        let time = Time::new(
+            s[0..2].parse().unwrap(),
+            s[3..5].parse().unwrap(),
+            s[6..8].parse().unwrap(),
+            s.get(9..).unwrap_or(""),
+        )
+        .ok_or_else(|| input.error(format!("Invalid time: '{}'", s)))?;
+        Ok(spanned(input, TimeLit(time)))
+    }
+
+    fn time_numoffset(input: ParseInput) -> ParseResult<Expr> {
+        let s = input.as_str();
+        let zone = TimeZone::new(
+            &s[0..1] == "+",
+            s[1..3].parse().unwrap(),
+            s[4..6].parse().unwrap(),
+        )
+        .ok_or_else(|| input.error(format!("Invalid time zone: '{}'", s)))?;
+        Ok(spanned(input, TimeZoneLit(zone)))
+    }
+
+    #[alias(time_numoffset)]
+    fn time_zone_utc(input: ParseInput) -> ParseResult<Expr> {
+        Ok(spanned(input, TimeZoneLit(TimeZone { minutes: 0 })))
+    }
+
     #[alias(expression, shortcut = true)]
     fn identifier(input: ParseInput) -> ParseResult<Expr> {
         Ok(match_nodes!(input.children();
@@ -645,6 +746,9 @@ impl DhallParser {
     fn toMap(_input: ParseInput) -> ParseResult<()> {
         Ok(())
     }
+    fn showConstructor(_input: ParseInput) -> ParseResult<()> {
+        Ok(())
+    }
 
     #[alias(expression)]
     fn empty_list_literal(input: ParseInput) -> ParseResult<Expr> {
@@ -716,26 +820,38 @@ impl DhallParser {
         op: ParseInput,
         r: Expr,
     ) -> ParseResult<Expr> {
-        use crate::syntax::BinOp::*;
-        use Rule::*;
-        let op = match op.as_rule() {
-            import_alt => ImportAlt,
-            bool_or => BoolOr,
-            natural_plus => NaturalPlus,
-            text_append => TextAppend,
-            list_append => ListAppend,
-            bool_and => BoolAnd,
-            combine => RecursiveRecordMerge,
-            prefer => RightBiasedRecordMerge,
-            combine_types => RecursiveRecordTypeMerge,
-            natural_times => NaturalTimes,
-            bool_eq => BoolEQ,
-            bool_ne => BoolNE,
-            equivalent => Equivalence,
-            r => Err(op.error(format!("Rule {:?} isn't an operator", r)))?,
-        };
+        binop_expression(l, op, r)
+    }
 
-        Ok(spanned_union(l.span(), r.span(), BinOp(op, l, r)))
+    #[alias(expression, shortcut = true)]
+    fn with_expression(input: ParseInput) -> ParseResult<Expr> {
+        Ok(match_nodes!(input.children();
+            [expression(e)] => e,
+            [expression(first), with_clause(clauses)..] => {
+                clauses.fold(
+                    first,
+                    |acc, (labels, e)| {
+                        spanned_union(
+                            acc.span(),
+                            e.span(),
+                            With(acc, labels, e),
+                        )
+                    }
+                )
+            },
+        ))
+    }
+
+    fn with_clause(input: ParseInput) -> ParseResult<(Vec<Label>, Expr)> {
+        Ok(match_nodes!(input.children();
+            [label(labels).., expression(e)] => (labels.collect(), e),
+        ))
+    }
+
+    #[alias(expression, shortcut = true)]
+    #[prec_climb(expression, PRECCLIMBER)]
+    fn with_value(l: Expr, op: ParseInput, r: Expr) -> ParseResult<Expr> {
+        binop_expression(l, op, r)
     }
 
     fn Some_(_input: ParseInput) -> ParseResult<()> {
@@ -773,6 +889,9 @@ impl DhallParser {
             [toMap(()), expression(x)] => {
                 spanned(input, ToMap(x, None))
             },
+            [showConstructor(()), expression(x)] => {
+                spanned(input, ShowConstructor(x))
+            },
             [expression(e)] => e,
         ))
     }
@@ -837,7 +956,9 @@ impl DhallParser {
     #[alias(expression, shortcut = true)]
     fn primitive_expression(input: ParseInput) -> ParseResult<Expr> {
         Ok(match_nodes!(input.children();
+            [temporal_literal(e)] => e,
             [double_literal(n)] => spanned(input, DoubleLit(n)),
+            [bytes_literal(b)] => spanned(input, BytesLit(b)),
             [natural_literal(n)] => spanned(input, NaturalLit(n)),
             [integer_literal(n)] => spanned(input, IntegerLit(n)),
             [double_quote_literal(s)] => spanned(input, TextLit(s)),
@@ -948,11 +1069,19 @@ impl DhallParser {
 }
 
 pub fn parse_expr(input_str: &str) -> ParseResult<Expr> {
-    let rc_input_str = input_str.to_string().into();
+    parse_expr_with_origin(input_str, "<current file>")
+}
+
+/// Parse an expression, recording in its spans that the text comes from `origin`.
+pub(crate) fn parse_expr_with_origin(
+    input_str: &str,
+    origin: impl Into<String>,
+) -> ParseResult<Expr> {
+    let source = Rc::new(Source::new(origin, input_str));
     let inputs = DhallParser::parse_with_userdata(
         Rule::final_expression,
         input_str,
-        rc_input_str,
+        source,
     )?;
     Ok(match_nodes!(<DhallParser>; inputs;
         [expression(e)] => e,
@@ -57,6 +57,7 @@ impl<E: Display + Clone> UnspannedExpr<E> {
                 b.phase(PrintPhase::BinOp(op)),
             ),
             SomeLit(e) => SomeLit(e.phase(PrintPhase::Import)),
+            ShowConstructor(e) => ShowConstructor(e.phase(PrintPhase::Import)),
             ExprKind::App(f, a) => ExprKind::App(
                 f.phase(PrintPhase::Import),
                 a.phase(PrintPhase::Import),
@@ -67,6 +68,12 @@ impl<E: Display + Clone> UnspannedExpr<E> {
             Completion(a, b) => {
                 Completion(a.phase(Primitive), b.phase(Primitive))
             }
+            // The value extends as far right as possible and cannot contain `with` itself.
+            With(a, ls, b) => With(
+                a.phase(PrintPhase::App),
+                ls,
+                b.phase(PrintPhase::BinOp(ast::BinOp::ImportAlt)),
+            ),
             e => e,
         }
     }
@@ -88,9 +95,11 @@ impl<E: Display + Clone> UnspannedExpr<E> {
             | SomeLit(_)
             | Merge(_, _, _)
             | ToMap(_, _)
+            | ShowConstructor(_)
             | Annot(_, _) => phase > PrintPhase::Base,
             // Precedence is magically handled by the ordering of BinOps.
             ExprKind::BinOp(op, _, _) => phase > PrintPhase::BinOp(*op),
+            With(_, _, _) => phase > PrintPhase::Operator,
             ExprKind::App(_, _) => phase > PrintPhase::App,
             Field(_, _)
             | Projection(_, _)
@@ -178,6 +187,9 @@ impl<SE: Display + Clone, E: Display> Display for ExprKind<SE, E> {
                     write!(f, " : {}", b)?;
                 }
             }
+            ShowConstructor(a) => {
+                write!(f, "showConstructor {}", a)?;
+            }
             Annot(a, b) => {
                 write!(f, "{} : {}", a, b)?;
             }
@@ -205,7 +217,11 @@ impl<SE: Display + Clone, E: Display> Display for ExprKind<SE, E> {
             }
             IntegerLit(a) => a.fmt(f)?,
             DoubleLit(a) => a.fmt(f)?,
+            DateLit(a) => a.fmt(f)?,
+            TimeLit(a) => a.fmt(f)?,
+            TimeZoneLit(a) => a.fmt(f)?,
             TextLit(a) => a.fmt(f)?,
+            BytesLit(a) => write!(f, "0x\"{}\"", hex::encode_upper(a))?,
             RecordType(a) if a.is_empty() => f.write_str("{}")?,
             RecordType(a) => fmt_list("{ ", ", ", " }", a, f, |(k, t), f| {
                 write!(f, "{} : {}", k, t)
@@ -231,6 +247,11 @@ impl<SE: Display + Clone, E: Display> Display for ExprKind<SE, E> {
             Completion(a, b) => {
                 write!(f, "{}::{}", a, b)?;
             }
+            With(a, ls, b) => {
+                write!(f, "{} with ", a)?;
+                fmt_list("", ".", "", ls, f, Display::fmt)?;
+                write!(f, " = {}", b)?;
+            }
             Import(a) => a.fmt(f)?,
             Embed(a) => a.fmt(f)?,
         }
@@ -342,6 +363,30 @@ impl Display for NaiveDouble {
     }
 }
 
+impl Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{:02}:{:02}:{:02}", self.hour, self.minute, self.second)?;
+        if !self.fraction.is_empty() {
+            write!(f, ".{}", self.fraction)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for TimeZone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let sign = if self.minutes < 0 { '-' } else { '+' };
+        let minutes = self.minutes.abs();
+        write!(f, "{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+    }
+}
+
 impl Display for Label {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         // TODO: distinguish between reserved and nonreserved locations for quoting builtins
@@ -372,8 +417,22 @@ impl<SubExpr: Display> Display for Import<SubExpr> {
         use FilePrefix::*;
         use ImportLocation::*;
         use ImportMode::*;
+        // See `path-character` in the grammar
+        let is_path_character = |c: char| match c {
+            '\u{21}'
+            | '\u{24}'..='\u{27}'
+            | '\u{2A}'..='\u{2B}'
+            | '\u{2D}'..='\u{2E}'
+            | '\u{30}'..='\u{3B}'
+            | '\u{3D}'
+            | '\u{40}'..='\u{5A}'
+            | '\u{5E}'..='\u{7A}'
+            | '\u{7C}'
+            | '\u{7E}' => true,
+            _ => false,
+        };
         let quote_if_needed = |s: &str| -> String {
-            if s.chars().all(|c| c.is_ascii_alphanumeric()) {
+            if !s.is_empty() && s.chars().all(is_path_character) {
                 s.to_string()
             } else {
                 format!("\"{}\"", s)
@@ -456,6 +515,10 @@ impl Display for Builtin {
             Integer => "Integer",
             Double => "Double",
             Text => "Text",
+            Bytes => "Bytes",
+            Date => "Date",
+            Time => "Time",
+            TimeZone => "TimeZone",
             List => "List",
             Optional => "Optional",
             OptionalNone => "None",
@@ -482,6 +545,11 @@ impl Display for Builtin {
             OptionalFold => "Optional/fold",
             OptionalBuild => "Optional/build",
             TextShow => "Text/show",
+            TextReplace => "Text/replace",
+            BytesShow => "Bytes/show",
+            DateShow => "Date/show",
+            TimeShow => "Time/show",
+            TimeZoneShow => "TimeZone/show",
         })
     }
 }
@@ -44,11 +44,15 @@ macro_rules! assert_eq_pretty_str {
 }
 
 use std::env;
-use std::fs::{create_dir_all, read_to_string, File};
+use std::fs::{
+    copy, create_dir_all, read_dir, read_to_string, remove_dir_all, File,
+};
 use std::io::{Read, Write};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use crate::error::{Error, Result};
+use crate::semantics::resolve::ImportResolver;
 use crate::syntax::binary;
 use crate::{Normalized, NormalizedExpr, Parsed, Resolved};
 
@@ -94,7 +98,9 @@ impl TestFile {
     }
     /// Parse and resolve the target file
     pub fn resolve(&self) -> Result<Resolved> {
-        Ok(self.parse()?.resolve()?)
+        Ok(self
+            .parse()?
+            .resolve_with(&ImportResolver::new().no_cache())?)
     }
     /// Parse, resolve, tck and normalize the target file
     pub fn normalize(&self) -> Result<Normalized> {
@@ -133,7 +139,7 @@ impl TestFile {
         let path = self.path();
         create_dir_all(path.parent().unwrap())?;
         let mut file = File::create(path)?;
-        writeln!(file, "{}", err)?;
+        writeln!(file, "{}", display_chain(&err))?;
         Ok(())
     }
 
@@ -219,7 +225,7 @@ impl TestFile {
         }
 
         let expected = read_to_string(self.path())?;
-        let msg = format!("{}\n", err);
+        let msg = format!("{}\n", display_chain(&err));
         if msg != expected {
             if Self::force_update() {
                 self.write_ui(err)?;
@@ -231,6 +237,35 @@ impl TestFile {
     }
 }
 
+/// Display an error followed by the errors it was caused by, one per line.
+fn display_chain(err: &Error) -> String {
+    let mut msg = err.to_string();
+    let mut source = std::error::Error::source(err);
+    while let Some(err) = source {
+        msg += &format!(":\n{}", err);
+        source = err.source();
+    }
+    msg
+}
+
+/// Copy the semantic cache of the spec tests to a fresh temporary directory.
+fn copy_test_cache() -> Result<PathBuf> {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let dir = env::temp_dir().join(format!(
+        "dhall-test-cache-{}-{}",
+        std::process::id(),
+        COUNT.fetch_add(1, Ordering::SeqCst)
+    ));
+    create_dir_all(&dir)?;
+    if let Ok(entries) = read_dir("../dhall-lang/tests/import/cache/dhall") {
+        for entry in entries {
+            let entry = entry?;
+            copy(entry.path(), dir.join(entry.file_name()))?;
+        }
+    }
+    Ok(dir)
+}
+
 #[allow(dead_code)]
 fn run_test_stringy_error(test: Test) -> std::result::Result<(), String> {
     run_test(test).map_err(|e| e.to_string())?;
@@ -274,11 +309,25 @@ fn run_test(test: Test) -> Result<()> {
             assert_eq!(expr, expected);
         }
         ImportSuccess(expr, expected) => {
-            let expr = expr.normalize()?;
-            expected.compare(expr)?;
+            // Some tests rely on entries of the semantic cache. Resolving may add entries, so
+            // work on a copy of it.
+            let cache_dir = copy_test_cache()?;
+            let resolver = ImportResolver::new().cache_dir(&cache_dir);
+            let result = (|| -> Result<Normalized> {
+                Ok(expr
+                    .parse()?
+                    .resolve_with(&resolver)?
+                    .typecheck()?
+                    .normalize())
+            })();
+            let _ = remove_dir_all(&cache_dir);
+            expected.compare(result?)?;
         }
         ImportFailure(expr, expected) => {
-            let err = expr.parse()?.resolve().unwrap_err();
+            let err = expr
+                .parse()?
+                .resolve_with(&ImportResolver::new().no_cache())
+                .unwrap_err();
             expected.compare_ui(err)?;
         }
         TypeInferenceSuccess(expr, expected) => {
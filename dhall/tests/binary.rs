@@ -0,0 +1,167 @@
+use dhall::Parsed;
+
+/// Check that encoding `src` and decoding the result gives back the same expression.
+fn roundtrip(src: &str) {
+    let parsed = Parsed::parse_str(src)
+        .unwrap_or_else(|e| panic!("failed to parse `{}`: {}", src, e));
+    let data = parsed
+        .encode()
+        .unwrap_or_else(|e| panic!("failed to encode `{}`: {}", src, e));
+    let decoded = Parsed::parse_binary(&data)
+        .unwrap_or_else(|e| panic!("failed to decode `{}`: {}", src, e));
+    assert_eq!(decoded, parsed, "`{}` changed after a round-trip", src);
+}
+
+#[test]
+fn roundtrip_every_kind() {
+    let exprs = [
+        // Constants and variables
+        "Type",
+        "Kind",
+        "Sort",
+        "x",
+        "x@2",
+        "_",
+        "_@1",
+        // Functions and bindings
+        "λ(x : Natural) → x",
+        "λ(_ : Natural) → _",
+        "∀(x : Type) → x",
+        "Natural → Natural",
+        "f x y",
+        "let x = 1 let y : Natural = 2 in x + y",
+        "1 : Natural",
+        "assert : 1 + 1 ≡ 2",
+        // Builtins
+        "Bool",
+        "Natural/fold",
+        "Natural/subtract",
+        "Integer/clamp",
+        "List/indexed",
+        "Optional",
+        "None",
+        "Text/replace",
+        "Bytes",
+        "Bytes/show",
+        "Date",
+        "Time",
+        "TimeZone",
+        "Date/show",
+        "Time/show",
+        "TimeZone/show",
+        // Literals
+        "True",
+        "if True then 1 else 2",
+        "0",
+        "12345678901234",
+        "+0",
+        "-1",
+        "+12345678901234",
+        "1.5",
+        "-0.0",
+        "1e100",
+        "NaN",
+        "Infinity",
+        "-Infinity",
+        "\"\"",
+        "\"foo\"",
+        "\"a${x}b${y}\"",
+        "0x\"\"",
+        "0x\"00FF\"",
+        "2020-02-29",
+        "0000-01-01",
+        "12:00:00",
+        "23:59:59.5",
+        "23:59:59.50",
+        "00:00:00.000000000001",
+        "23:59:59.999999999999",
+        "23:59:59.1234567890123456789",
+        "00:00:00.00000000000000000000000000000000000000001",
+        "+01:00",
+        "-05:30",
+        "+00:00",
+        "2020-01-01T12:00:00",
+        "12:00:00+01:00",
+        "2020-01-01T12:00:00.25-02:00",
+        "[] : List Natural",
+        "[1, 2, 3]",
+        "Some 1",
+        // Records and unions
+        "{}",
+        "{ x : Natural, y : Bool }",
+        "{=}",
+        "{ x = 1, y = True }",
+        "{ `x y` = 1 }",
+        "< A | B : Natural >",
+        "< A >.A",
+        "r.x",
+        "r.{ x, y }",
+        "r.({ x : Natural })",
+        "merge { A = 1 } < A >.A",
+        "merge { A = 1 } < A >.A : Natural",
+        "toMap { x = 1 }",
+        "toMap {=} : List { mapKey : Text, mapValue : Natural }",
+        "showConstructor (< A >.A)",
+        "showConstructor (Some 1)",
+        "T::{ x = 1 }",
+        "r with x = 1",
+        "r with x.y.z = 1",
+        // Operators
+        "a || b",
+        "a + b",
+        "a ++ b",
+        "a # b",
+        "a && b",
+        "a ∧ b",
+        "a ⫽ b",
+        "a ⩓ b",
+        "a * b",
+        "a == b",
+        "a != b",
+        "a ≡ b",
+        "a ? b",
+        // Imports
+        "./foo.dhall",
+        "../foo/bar.dhall",
+        "/foo/bar.dhall",
+        "~/foo.dhall",
+        "https://example.com/foo.dhall",
+        "https://user@example.com:8080/foo.dhall?x=1",
+        "https://example.com/foo.dhall using [{ mapKey = \"a\", mapValue = \"b\" }]",
+        "env:FOO",
+        "env:\"FOO BAR\"",
+        "missing",
+        "./foo.dhall as Text",
+        "./foo.dhall as Location",
+        "./foo.dhall sha256:0000000000000000000000000000000000000000000000000000000000000000",
+    ];
+    for src in exprs.iter() {
+        roundtrip(src);
+    }
+}
+
+#[test]
+fn decode_time_fraction() {
+    // `[101, 12, 0, 4([-13, 1])]`
+    let data = [0x84, 0x18, 0x65, 0x0C, 0x00, 0xC4, 0x82, 0x2C, 0x01];
+    assert_eq!(
+        Parsed::parse_binary(&data).unwrap(),
+        Parsed::parse_str("12:00:00.0000000000001").unwrap()
+    );
+    // `[101, 12, 0, 4([-20, 2(h'013FD6F2B475D4B00001')])]`: the mantissa needs a bignum
+    let data = [
+        0x84, 0x18, 0x65, 0x0C, 0x00, 0xC4, 0x82, 0x33, 0xC2, 0x4A, 0x01, 0x3F,
+        0xD6, 0xF2, 0xB4, 0x75, 0xD4, 0xB0, 0x00, 0x01,
+    ];
+    assert_eq!(
+        Parsed::parse_binary(&data).unwrap(),
+        Parsed::parse_str("12:00:59.00000000000000000001").unwrap()
+    );
+    assert_eq!(
+        Parsed::parse_str("12:00:59.00000000000000000001")
+            .unwrap()
+            .encode()
+            .unwrap(),
+        data
+    );
+}
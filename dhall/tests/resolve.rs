@@ -0,0 +1,561 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use dhall::error::{Error, ImportError};
+use dhall::semantics::resolve::{
+    FetchResponse, Fetcher, ImportPolicy, ImportResolver, MemoryFetcher,
+};
+use dhall::Parsed;
+
+/// Resolve, typecheck and normalize `src`, returning the result as text.
+fn resolve(
+    src: &str,
+    resolver: &ImportResolver,
+) -> Result<String, ImportError> {
+    let resolved = Parsed::parse_str(src).unwrap().resolve_with(resolver)?;
+    Ok(resolved.typecheck().unwrap().normalize().to_string())
+}
+
+/// The error that caused resolution to fail, looking through the imports that led to it.
+fn root_cause(err: &ImportError) -> &ImportError {
+    match err {
+        ImportError::Recursive(_, err) => match err.as_ref() {
+            Error::Resolve(err) => root_cause(err),
+            err => panic!("unexpected error: {}", err),
+        },
+        err => err,
+    }
+}
+
+/// A directory that is removed when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "dhall_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+
+    fn write(&self, file: &str, contents: &str) -> PathBuf {
+        let path = self.0.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn remote(fetcher: MemoryFetcher) -> ImportResolver {
+    ImportResolver::new().no_cache().fetcher(fetcher)
+}
+
+#[test]
+fn memory_fetcher() {
+    let mut fetcher = MemoryFetcher::new();
+    fetcher.insert("https://example.com/a.dhall", "1");
+    fetcher.insert_response(
+        "https://example.com/b.dhall",
+        FetchResponse::new("2").with_header("X-Foo", "bar"),
+    );
+
+    let response = fetcher.fetch("https://example.com/b.dhall", &[]).unwrap();
+    assert_eq!(response.body, b"2");
+    assert_eq!(response.header("x-foo"), Some("bar"));
+    assert_eq!(response.header("X-Bar"), None);
+    assert!(fetcher.fetch("https://example.com/c.dhall", &[]).is_err());
+
+    let resolver = remote(fetcher);
+    assert_eq!(
+        resolve("https://example.com/a.dhall + 1", &resolver).unwrap(),
+        "2"
+    );
+    // Relative imports inside a remote file are fetched from the same server
+    let mut fetcher = MemoryFetcher::new();
+    fetcher.insert("https://example.com/dir/a.dhall", "./b.dhall + 1");
+    fetcher.insert("https://example.com/dir/b.dhall", "1");
+    let resolver = remote(fetcher);
+    assert_eq!(
+        resolve("https://example.com/dir/a.dhall", &resolver).unwrap(),
+        "2"
+    );
+    // Unknown urls fail to resolve
+    let err = resolve("https://example.com/a.dhall", &resolver).unwrap_err();
+    match &err {
+        ImportError::Recursive(_, inner) => match inner.as_ref() {
+            Error::IO(inner) => {
+                // The nested error is reported as the source, not displayed as well
+                assert!(!err.to_string().contains(&inner.to_string()));
+                let source = std::error::Error::source(&err).unwrap();
+                assert_eq!(source.to_string(), inner.to_string());
+                let source = source.downcast_ref::<Error>().unwrap();
+                assert!(matches!(source, Error::IO(_)));
+            }
+            err => panic!("unexpected error: {}", err),
+        },
+        err => panic!("unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn cors() {
+    let mut fetcher = MemoryFetcher::new();
+    fetcher.insert(
+        "https://example.com/a.dhall",
+        "https://other.example/b.dhall",
+    );
+    fetcher.insert("https://other.example/b.dhall", "1");
+    let err =
+        resolve("https://example.com/a.dhall", &remote(fetcher)).unwrap_err();
+    match root_cause(&err) {
+        ImportError::CorsDisallowed(origin, _) => {
+            assert_eq!(origin, "https://example.com")
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+
+    for allowed in &["*", "https://example.com"] {
+        let mut fetcher = MemoryFetcher::new();
+        fetcher.insert(
+            "https://example.com/a.dhall",
+            "https://other.example/b.dhall",
+        );
+        fetcher.insert_response(
+            "https://other.example/b.dhall",
+            FetchResponse::new("1")
+                .with_header("Access-Control-Allow-Origin", *allowed),
+        );
+        assert_eq!(
+            resolve("https://example.com/a.dhall", &remote(fetcher)).unwrap(),
+            "1"
+        );
+    }
+
+    // Another origin is not enough
+    let mut fetcher = MemoryFetcher::new();
+    fetcher.insert(
+        "https://example.com/a.dhall",
+        "https://other.example/b.dhall",
+    );
+    fetcher.insert_response(
+        "https://other.example/b.dhall",
+        FetchResponse::new("1").with_header(
+            "Access-Control-Allow-Origin",
+            "https://third.example",
+        ),
+    );
+    let err =
+        resolve("https://example.com/a.dhall", &remote(fetcher)).unwrap_err();
+    match root_cause(&err) {
+        ImportError::CorsDisallowed(_, _) => {}
+        err => panic!("unexpected error: {}", err),
+    }
+
+    // Imports from the top-level expression are not checked
+    let mut fetcher = MemoryFetcher::new();
+    fetcher.insert("https://other.example/b.dhall", "1");
+    assert_eq!(
+        resolve("https://other.example/b.dhall", &remote(fetcher)).unwrap(),
+        "1"
+    );
+}
+
+#[test]
+fn sanity_check() {
+    for import in &["/etc/passwd", "~/foo.dhall", "env:HOME"] {
+        let mut fetcher = MemoryFetcher::new();
+        fetcher.insert("https://example.com/a.dhall", *import);
+        let err = resolve("https://example.com/a.dhall", &remote(fetcher))
+            .unwrap_err();
+        match root_cause(&err) {
+            ImportError::SanityCheck(_) => {}
+            err => panic!("unexpected error for `{}`: {}", import, err),
+        }
+    }
+
+    // Remote files may still ask for the location of local imports
+    let mut fetcher = MemoryFetcher::new();
+    fetcher.insert("https://example.com/a.dhall", "/etc/passwd as Location");
+    assert_eq!(
+        resolve("https://example.com/a.dhall", &remote(fetcher)).unwrap(),
+        "< Environment: Text | Local: Text | Missing | Remote: Text >.Local \
+         \"/etc/passwd\""
+    );
+}
+
+#[test]
+fn env() {
+    let mut env = HashMap::new();
+    env.insert("FOO".to_owned(), "1 + 1".to_owned());
+    env.insert("BAR".to_owned(), "Natural".to_owned());
+    let resolver = ImportResolver::new().no_cache().env(env);
+    assert_eq!(resolve("env:FOO : env:BAR", &resolver).unwrap(), "2");
+    assert_eq!(resolve("env:FOO as Text", &resolver).unwrap(), "\"1 + 1\"");
+
+    let err = resolve("env:BAZ", &resolver).unwrap_err();
+    match &err {
+        ImportError::MissingEnvVar(import) => {
+            assert_eq!(import.to_string(), "env:BAZ")
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+    // A missing variable falls back to the right side of `?`
+    assert_eq!(resolve("env:BAZ ? env:FOO", &resolver).unwrap(), "2");
+    assert_eq!(resolve("env:BAZ ? 3", &resolver).unwrap(), "3");
+
+    // Relative imports inside variables are resolved against the root directory
+    let dir = TempDir::new("env");
+    dir.write("a.dhall", "1");
+    let mut env = HashMap::new();
+    env.insert("FOO".to_owned(), "./a.dhall + 1".to_owned());
+    let resolver = ImportResolver::new()
+        .no_cache()
+        .env(env)
+        .root_dir(dir.path());
+    assert_eq!(resolve("env:FOO", &resolver).unwrap(), "2");
+}
+
+#[test]
+fn resolver_is_send_sync() {
+    let mut fetcher = MemoryFetcher::new();
+    fetcher.insert("https://example.com/a.dhall", "1");
+    let resolver = std::sync::Arc::new(remote(fetcher));
+    let threads: Vec<_> = (0..2)
+        .map(|_| {
+            let resolver = resolver.clone();
+            std::thread::spawn(move || {
+                resolve("https://example.com/a.dhall", &resolver).unwrap()
+            })
+        })
+        .collect();
+    for thread in threads {
+        assert_eq!(thread.join().unwrap(), "1");
+    }
+}
+
+#[test]
+fn missing() {
+    let resolver = ImportResolver::new().no_cache();
+    match resolve("missing", &resolver).unwrap_err() {
+        ImportError::Missing => {}
+        err => panic!("unexpected error: {}", err),
+    }
+    assert_eq!(resolve("missing ? 1", &resolver).unwrap(), "1");
+    assert_eq!(
+        resolve("missing as Location", &resolver).unwrap(),
+        "< Environment: Text | Local: Text | Missing | Remote: Text >.Missing"
+    );
+}
+
+#[test]
+fn local() {
+    let dir = TempDir::new("local");
+    let file = dir.write("a/b.dhall", "../c.dhall + 1");
+    dir.write("c.dhall", "1");
+    let resolver = ImportResolver::new().no_cache();
+
+    let src = format!("{} + 1", file.display());
+    assert_eq!(resolve(&src, &resolver).unwrap(), "3");
+    let parsed = Parsed::parse_file(&file).unwrap();
+    let resolved = parsed.resolve_with(&resolver).unwrap();
+    assert_eq!(resolved.typecheck().unwrap().normalize().to_string(), "2");
+
+    let src = format!("{}/a/../c.dhall as Location", dir.path().display());
+    assert_eq!(
+        resolve(&src, &resolver).unwrap(),
+        format!(
+            "< Environment: Text | Local: Text | Missing | Remote: Text \
+             >.Local \"{}/c.dhall\"",
+            dir.path().display()
+        )
+    );
+    let src = format!("{}/d.dhall", dir.path().display());
+    assert!(resolve(&src, &resolver).is_err());
+}
+
+#[test]
+fn home() {
+    let dir = TempDir::new("home");
+    dir.write("a/b.dhall", "./c.dhall + 1");
+    dir.write("a/c.dhall", "1");
+    let resolver = ImportResolver::new().no_cache().home_dir(dir.path());
+    assert_eq!(resolve("~/a/b.dhall", &resolver).unwrap(), "2");
+    assert_eq!(
+        resolve("~/a/b.dhall as Location", &resolver).unwrap(),
+        "< Environment: Text | Local: Text | Missing | Remote: Text >.Local \
+         \"~/a/b.dhall\""
+    );
+    assert!(resolve("~/a/d.dhall", &resolver).is_err());
+}
+
+#[test]
+fn fallback() {
+    let mut fetcher = MemoryFetcher::new();
+    fetcher.insert("https://example.com/bad.dhall", "1 + True");
+    fetcher.insert("https://example.com/cycle.dhall", "./cycle.dhall");
+    let resolver = remote(fetcher).env(HashMap::new());
+
+    assert_eq!(resolve("missing ? missing ? 2", &resolver).unwrap(), "2");
+    assert_eq!(resolve("(missing ? 1) ? 2", &resolver).unwrap(), "1");
+    assert_eq!(resolve("missing ? (missing ? 2)", &resolver).unwrap(), "2");
+
+    // The errors of nested alternatives are flattened
+    for src in &[
+        "missing ? env:FOO ? missing",
+        "(missing ? env:FOO) ? missing",
+        "missing ? (env:FOO ? missing)",
+    ] {
+        match resolve(src, &resolver).unwrap_err() {
+            ImportError::MissingImports(errs) => {
+                let errs: Vec<_> = errs.iter().map(|e| e.to_string()).collect();
+                assert_eq!(errs.len(), 3, "{}", src);
+                assert!(
+                    matches!(errs.as_slice(), [_, e, _] if e.contains("env:FOO"))
+                );
+            }
+            err => panic!("unexpected error for `{}`: {}", src, err),
+        }
+    }
+
+    // Type errors and import cycles do not fall back
+    let err =
+        resolve("https://example.com/bad.dhall ? 1", &resolver).unwrap_err();
+    match &err {
+        ImportError::Recursive(_, err) => match err.as_ref() {
+            Error::Typecheck(_) => {}
+            err => panic!("unexpected error: {}", err),
+        },
+        err => panic!("unexpected error: {}", err),
+    }
+    let err =
+        resolve("https://example.com/cycle.dhall ? 1", &resolver).unwrap_err();
+    match root_cause(&err) {
+        ImportError::ImportCycle(_, _) => {}
+        err => panic!("unexpected error: {}", err),
+    }
+    assert!(
+        resolve("missing ? https://example.com/bad.dhall ? 1", &resolver)
+            .is_err()
+    );
+}
+
+#[test]
+fn hash() {
+    let zeros = "0".repeat(64);
+    let mut fetcher = MemoryFetcher::new();
+    fetcher.insert("https://example.com/a.dhall", "1 + 1");
+    let resolver = remote(fetcher);
+
+    let src = format!("https://example.com/a.dhall sha256:{}", zeros);
+    let actual = match resolve(&src, &resolver).unwrap_err() {
+        ImportError::HashMismatch(import, actual) => {
+            assert_eq!(import.to_string(), src);
+            actual
+        }
+        err => panic!("unexpected error: {}", err),
+    };
+    // The import is checked against the hash of its normal form
+    let src = format!("https://example.com/a.dhall {}", actual);
+    assert_eq!(resolve(&src, &resolver).unwrap(), "2");
+    // A mismatch falls back to the right side of `?`
+    let src = format!("https://example.com/a.dhall sha256:{} ? 3", zeros);
+    assert_eq!(resolve(&src, &resolver).unwrap(), "3");
+}
+
+#[test]
+fn cache() {
+    let dir = TempDir::new("cache");
+    let cache_dir = dir.path().join("cache");
+    let mut fetcher = MemoryFetcher::new();
+    fetcher.insert("https://example.com/a.dhall", "1 + 1");
+    let src = format!("https://example.com/a.dhall sha256:{}", "0".repeat(64));
+    let hash = match resolve(&src, &remote(fetcher.clone())).unwrap_err() {
+        ImportError::HashMismatch(_, actual) => actual,
+        err => panic!("unexpected error: {}", err),
+    };
+    let src = format!("https://example.com/a.dhall {}", hash);
+    let entry = cache_dir.join(format!("1220{}", &hash.to_string()[7..]));
+
+    // Without a cache nothing is written
+    resolve(&src, &remote(fetcher.clone())).unwrap();
+    assert!(!cache_dir.exists());
+    // A hashed import is stored under its hash
+    let resolver = ImportResolver::new()
+        .cache_dir(&cache_dir)
+        .fetcher(fetcher.clone());
+    assert_eq!(resolve(&src, &resolver).unwrap(), "2");
+    assert!(entry.is_file());
+    // ... and read back without fetching it
+    let offline = ImportResolver::new()
+        .cache_dir(&cache_dir)
+        .fetcher(MemoryFetcher::new());
+    assert_eq!(resolve(&src, &offline).unwrap(), "2");
+    // A corrupted entry is ignored, and replaced by the fetched expression
+    let data = std::fs::read(&entry).unwrap();
+    std::fs::write(&entry, b"garbage").unwrap();
+    assert!(resolve(&src, &offline).is_err());
+    assert_eq!(resolve(&src, &resolver).unwrap(), "2");
+    assert_eq!(std::fs::read(&entry).unwrap(), data);
+    // The policy applies to cached imports too
+    let offline = offline.policy(ImportPolicy::new().allow_remote(false));
+    assert!(forbidden(&src, &offline));
+}
+
+fn forbidden(src: &str, resolver: &ImportResolver) -> bool {
+    match resolve(src, resolver) {
+        Err(err) => match root_cause(&err) {
+            ImportError::Forbidden(_) => true,
+            err => panic!("unexpected error for `{}`: {}", src, err),
+        },
+        Ok(_) => false,
+    }
+}
+
+#[test]
+fn policy_kinds() {
+    let mut fetcher = MemoryFetcher::new();
+    fetcher.insert("https://example.com/a.dhall", "1");
+    let mut env = HashMap::new();
+    env.insert("FOO".to_owned(), "1".to_owned());
+    let resolver =
+        |policy| remote(fetcher.clone()).env(env.clone()).policy(policy);
+
+    let policy = ImportPolicy::new().allow_remote(false);
+    assert!(forbidden(
+        "https://example.com/a.dhall",
+        &resolver(policy.clone())
+    ));
+    assert!(!forbidden("env:FOO", &resolver(policy.clone())));
+    // Forbidden imports fall back to the right side of `?`
+    assert_eq!(
+        resolve("https://example.com/a.dhall ? 2", &resolver(policy)).unwrap(),
+        "2"
+    );
+
+    let policy = ImportPolicy::new().allow_env(false);
+    assert!(forbidden("env:FOO", &resolver(policy.clone())));
+    assert!(!forbidden("https://example.com/a.dhall", &resolver(policy)));
+
+    let policy = ImportPolicy::new().allow_local(false);
+    assert!(forbidden("/etc/passwd as Text", &resolver(policy.clone())));
+    // Imports that read nothing are always allowed
+    assert!(!forbidden(
+        "/etc/passwd as Location",
+        &resolver(policy.clone())
+    ));
+    assert!(!forbidden("missing ? 1", &resolver(policy)));
+}
+
+#[test]
+fn policy_local_root() {
+    let dir = TempDir::new("policy_local_root");
+    let root = dir.path().join("root");
+    let file = dir.write("root/a.dhall", "./b/c.dhall");
+    dir.write("root/b/c.dhall", "../d.dhall");
+    dir.write("root/d.dhall", "1");
+    dir.write("root/escape.dhall", "../outside.dhall");
+    dir.write(
+        "root/absolute.dhall",
+        &format!("{}/outside.dhall", dir.path().display()),
+    );
+    dir.write("outside.dhall", "1");
+    let resolver = ImportResolver::new()
+        .no_cache()
+        .policy(ImportPolicy::new().local_root(&root));
+
+    let src = |file: &str| root.join(file).display().to_string();
+    let parsed = Parsed::parse_file(&file).unwrap();
+    assert!(parsed.resolve_with(&resolver).is_ok());
+    assert!(!forbidden(&src("a.dhall"), &resolver));
+    assert!(forbidden(&src("escape.dhall"), &resolver));
+    assert!(forbidden(&src("absolute.dhall"), &resolver));
+    assert!(forbidden(&src("b/../../outside.dhall"), &resolver));
+    // A root directory that shares a prefix with the file is not enough
+    dir.write("root2/e.dhall", "1");
+    assert!(forbidden(&src("../root2/e.dhall"), &resolver));
+}
+
+#[test]
+fn policy_limits() {
+    let mut fetcher = MemoryFetcher::new();
+    fetcher.insert("https://example.com/1.dhall", "./2.dhall");
+    fetcher.insert("https://example.com/2.dhall", "./3.dhall");
+    fetcher.insert("https://example.com/3.dhall", "1");
+    fetcher.insert("https://example.com/pair.dhall", "./3.dhall + ./3.dhall");
+    let resolver = |policy| remote(fetcher.clone()).policy(policy);
+
+    // The top-level import has depth 1
+    let src = "https://example.com/1.dhall";
+    assert!(!forbidden(src, &resolver(ImportPolicy::new().max_depth(3))));
+    assert!(forbidden(src, &resolver(ImportPolicy::new().max_depth(2))));
+
+    assert!(!forbidden(
+        src,
+        &resolver(ImportPolicy::new().max_imports(3))
+    ));
+    assert!(forbidden(
+        src,
+        &resolver(ImportPolicy::new().max_imports(2))
+    ));
+    // Importing the same file twice counts once
+    let src = "https://example.com/pair.dhall + https://example.com/3.dhall";
+    assert!(!forbidden(
+        src,
+        &resolver(ImportPolicy::new().max_imports(2))
+    ));
+    assert!(forbidden(
+        src,
+        &resolver(ImportPolicy::new().max_imports(1))
+    ));
+    // `as Location` imports count too
+    let src = "let x = ./a.dhall as Location in https://example.com/3.dhall";
+    assert!(!forbidden(
+        src,
+        &resolver(ImportPolicy::new().max_imports(2))
+    ));
+    assert!(forbidden(
+        src,
+        &resolver(ImportPolicy::new().max_imports(1))
+    ));
+}
+
+#[test]
+fn type_error_in_import() {
+    let dir = TempDir::new("type_error_in_import");
+    let file = dir.write("a.dhall", "./b.dhall");
+    dir.write("b.dhall", "./c.dhall");
+    dir.write("c.dhall", "1 + True");
+    let resolver = ImportResolver::new().no_cache();
+    let err = Parsed::parse_file(&file)
+        .unwrap()
+        .resolve_with(&resolver)
+        .unwrap_err();
+    // The type error is at the end of the chain of imports
+    let mut err: &dyn std::error::Error = &err;
+    while let Some(source) = err.source() {
+        err = source;
+    }
+    let msg = err.to_string();
+    let c = dir.path().join("c.dhall").display().to_string();
+    let b = dir.path().join("b.dhall").display().to_string();
+    assert!(msg.contains(&format!("--> {}:1:", c)), "{}", msg);
+    assert!(
+        msg.contains(&format!("while checking the import `{}`", c)),
+        "{}",
+        msg
+    );
+    assert!(msg.contains(&format!("imported by `{}`", b)), "{}", msg);
+}
@@ -11,8 +11,7 @@
 //! YAML. It uses the [Serde][serde] serialization library to provide drop-in support for Dhall
 //! for any datatype that supports serde (and that's a lot of them !).
 //!
-//! This library is limited to deserializing (reading) Dhall values; serializing (writing)
-//! values to Dhall is not supported.
+//! Rust values can also be written out as Dhall text; see [Serialization](#serialization).
 //!
 //! # Basic usage
 //!
@@ -75,6 +74,8 @@
 //! `Integer`  | `i64`, `i32`, ...
 //! `Double`  | `f64`, `f32`, ...
 //! `Text`  | `String`
+//! `Bytes`  | `Vec<u8>`, `serde_bytes::ByteBuf`
+//! `Date`, `Time`, `TimeZone`  | `String`, e.g. `"2020-01-31"`, `"09:30:00.5"`, `"+01:00"`
 //! `List T`  | `Vec<T>`
 //! `Optional T`  | `Option<T>`
 //! `{ x: T, y: U }`  | structs
@@ -82,8 +83,8 @@
 //! `{ x: T, y: T }`  | `HashMap<String, T>`, structs
 //! `< x: T \| y: U >`  | enums
 //! `T -> U`  | unsupported
-//! `Prelude.JSON.Type`  | unsupported
-//! `Prelude.Map.Type T U`  | unsupported
+//! `Prelude.JSON.Type`  | `serde_json::Value`, and other types that accept any data
+//! `Prelude.Map.Type T U`  | `HashMap<T, U>`, `BTreeMap<T, U>`
 //!
 //!
 //! # Replacing `serde_json` or `serde_yaml`
@@ -163,17 +164,101 @@
 //! # }
 //! ```
 //!
+//! # Import resolution
+//!
+//! Imports are resolved relative to the current directory, from the process environment and over
+//! HTTP. Files read with [`from_file`][from_file] resolve their relative imports against the
+//! directory that contains them instead. To change that, build an
+//! [`ImportResolver`][ImportResolver] and pass it to
+//! [`from_str_with_resolver`][from_str_with_resolver]. An [`ImportPolicy`][ImportPolicy] restricts
+//! the kinds of imports that are allowed.
+//!
+//! The [`options`][fn@options] builder combines all of these settings: the source of the data, the
+//! resolver, whether imports are allowed at all, the expected type, and whether to reject record
+//! fields that the Rust type doesn't have.
+//!
+//! ```rust
+//! # fn main() -> serde_dhall::de::Result<()> {
+//! use std::collections::HashMap;
+//! use serde_dhall::{ImportPolicy, ImportResolver};
+//!
+//! let mut env = HashMap::new();
+//! env.insert("PORT".to_string(), "8080".to_string());
+//! let resolver = ImportResolver::new()
+//!     .env(env)
+//!     .policy(ImportPolicy::new().allow_remote(false));
+//!
+//! let port: u64 = serde_dhall::from_str_with_resolver("env:PORT", &resolver)?;
+//! assert_eq!(port, 8080);
+//!
+//! // Remote imports are forbidden by the policy
+//! let data = "https://example.com/port.dhall";
+//! assert!(serde_dhall::from_str_with_resolver::<u64>(data, &resolver).is_err());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Serialization
+//!
+//! The [`to_string`][to_string] function turns any serde-compatible value into Dhall text. Structs
+//! and maps become records, tuples become records with fields `_1`, `_2`, ..., enums become union
+//! values and `Option`s become `Some x` or `None T`.
+//!
+//! Empty lists and `None`s must be annotated with their type, which cannot always be guessed from
+//! the value. For types that implement [`StaticType`][StaticType],
+//! [`to_string_auto_type`][to_string_auto_type] uses the static type of the value for these
+//! annotations.
+//!
+//! ```rust
+//! # fn main() -> serde_dhall::de::Result<()> {
+//! use serde::Serialize;
+//! use serde_dhall::StaticType;
+//!
+//! #[derive(Serialize, StaticType)]
+//! struct Point {
+//!     x: u64,
+//!     y: u64,
+//! }
+//!
+//! let point = Point { x: 1, y: 2 };
+//! assert_eq!(serde_dhall::to_string(&point)?, "{ x = 1, y = 2 }");
+//!
+//! let points: Vec<Point> = Vec::new();
+//! assert!(serde_dhall::to_string(&points).is_err());
+//! assert_eq!(
+//!     serde_dhall::to_string_auto_type(&points)?,
+//!     "[] : List { x : Natural, y : Natural }"
+//! );
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! [dhall]: https://dhall-lang.org/
 //! [serde]: https://docs.serde.rs/serde/
 //! [serde::Deserialize]: https://docs.serde.rs/serde/trait.Deserialize.html
 
+mod options;
 mod serde;
+mod serialize;
 mod static_type;
 
 #[doc(inline)]
-pub use de::{from_str, from_str_auto_type, from_str_check_type};
+pub use de::{
+    from_binary, from_file, from_file_auto_type, from_file_check_type,
+    from_reader, from_str, from_str_auto_type, from_str_check_type,
+    from_str_with_resolver,
+};
+#[doc(inline)]
+pub use de::{options, Deserializer, Options};
+#[doc(inline)]
+pub use dhall::semantics::resolve::{
+    Environment, FetchResponse, Fetcher, ImportPolicy, ImportResolver,
+    MemoryFetcher,
+};
 #[doc(hidden)]
 pub use dhall_proc_macros::StaticType;
+#[doc(inline)]
+pub use ser::{to_string, to_string_auto_type};
 pub use static_type::StaticType;
 #[doc(inline)]
 pub use value::Value;
@@ -181,10 +266,11 @@ pub use value::Value;
 // A Dhall value.
 #[doc(hidden)]
 pub mod value {
+    use dhall::semantics::resolve::ImportResolver;
     use dhall::syntax::Builtin;
     use dhall::{Normalized, NormalizedExpr, Parsed};
 
-    use super::de::{Error, Result};
+    use super::de::Result;
 
     /// A Dhall value
     #[derive(Debug, Clone, PartialEq, Eq)]
@@ -192,13 +278,23 @@ pub mod value {
 
     impl Value {
         pub fn from_str(s: &str, ty: Option<&Value>) -> Result<Self> {
-            Value::from_str_using_dhall_error_type(s, ty).map_err(Error::Dhall)
+            let de = crate::options().string(s);
+            match ty {
+                None => de.parse(),
+                Some(ty) => de.type_annotation(ty).parse(),
+            }
         }
-        fn from_str_using_dhall_error_type(
-            s: &str,
+        /// Resolve imports with `resolver`, or reject them if it is `None`, then typecheck and
+        /// normalize.
+        pub(crate) fn from_parsed(
+            parsed: Parsed,
             ty: Option<&Value>,
+            resolver: Option<&ImportResolver>,
         ) -> dhall::error::Result<Self> {
-            let resolved = Parsed::parse_str(s)?.resolve()?;
+            let resolved = match resolver {
+                Some(resolver) => parsed.resolve_with(resolver)?,
+                None => parsed.skip_resolve()?,
+            };
             let typed = match ty {
                 None => resolved.typecheck()?,
                 Some(t) => resolved.typecheck_with(t.as_normalized())?,
@@ -249,10 +345,16 @@ pub mod value {
 
 /// Deserialize Dhall data to a Rust data structure.
 pub mod de {
+    use std::io::Read;
+    use std::path::Path;
+
     use super::StaticType;
     use super::Value;
+    use dhall::semantics::resolve::ImportResolver;
     pub use error::{Error, Result};
 
+    pub use crate::options::{options, Deserializer, Options};
+
     mod error {
         use dhall::error::Error as DhallError;
 
@@ -263,6 +365,7 @@ pub mod de {
         pub enum Error {
             Dhall(DhallError),
             Deserialize(String),
+            Serialize(String),
         }
 
         impl std::fmt::Display for Error {
@@ -270,11 +373,19 @@ pub mod de {
                 match self {
                     Error::Dhall(err) => write!(f, "{}", err),
                     Error::Deserialize(err) => write!(f, "{}", err),
+                    Error::Serialize(err) => write!(f, "{}", err),
                 }
             }
         }
 
-        impl std::error::Error for Error {}
+        impl std::error::Error for Error {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                match self {
+                    Error::Dhall(err) => err.source(),
+                    Error::Deserialize(_) | Error::Serialize(_) => None,
+                }
+            }
+        }
 
         impl serde::de::Error for Error {
             fn custom<T>(msg: T) -> Self
@@ -284,6 +395,15 @@ pub mod de {
                 Error::Deserialize(msg.to_string())
             }
         }
+
+        impl serde::ser::Error for Error {
+            fn custom<T>(msg: T) -> Self
+            where
+                T: std::fmt::Display,
+            {
+                Error::Serialize(msg.to_string())
+            }
+        }
     }
 
     pub(crate) mod sealed {
@@ -299,19 +419,26 @@ pub mod de {
     pub trait Deserialize: sealed::Sealed + Sized {
         /// See [serde_dhall::from_str][crate::from_str]
         fn from_dhall(v: &Value) -> Result<Self>;
+
+        /// Like `from_dhall`, but record fields that `Self` doesn't have are an error. See
+        /// [Deserializer::strict][crate::Deserializer::strict].
+        #[doc(hidden)]
+        fn from_dhall_strict(v: &Value) -> Result<Self> {
+            Self::from_dhall(v)
+        }
     }
 
     /// Deserialize an instance of type `T` from a string of Dhall text.
     ///
     /// This will recursively resolve all imports in the expression, and
     /// typecheck it before deserialization. Relative imports will be resolved relative to the
-    /// provided file. More control over this process is not yet available
-    /// but will be in a coming version of this crate.
+    /// current directory; use [from_file] to resolve them relative to a file, or
+    /// [from_str_with_resolver] for more control over this process.
     pub fn from_str<T>(s: &str) -> Result<T>
     where
         T: Deserialize,
     {
-        T::from_dhall(&Value::from_str(s, None)?)
+        crate::options().string(s).parse()
     }
 
     /// Deserialize an instance of type `T` from a string of Dhall text,
@@ -323,7 +450,7 @@ pub mod de {
     where
         T: Deserialize,
     {
-        T::from_dhall(&Value::from_str(s, Some(ty))?)
+        crate::options().string(s).type_annotation(ty).parse()
     }
 
     /// Deserialize an instance of type `T` from a string of Dhall text,
@@ -336,6 +463,117 @@ pub mod de {
     where
         T: Deserialize + StaticType,
     {
-        from_str_check_type(s, &<T as StaticType>::static_type())
+        crate::options().string(s).parse_auto_type()
+    }
+
+    /// Deserialize an instance of type `T` from a string of Dhall text, resolving imports with
+    /// the provided resolver.
+    ///
+    /// Like [from_str], but the resolver decides which imports are allowed, what relative imports
+    /// are relative to, and where remote imports and environment variables come from.
+    pub fn from_str_with_resolver<T>(
+        s: &str,
+        resolver: &ImportResolver,
+    ) -> Result<T>
+    where
+        T: Deserialize,
+    {
+        crate::options().string(s).resolver(resolver).parse()
+    }
+
+    /// Deserialize an instance of type `T` from a file of Dhall text.
+    ///
+    /// Like [from_str], but relative imports are resolved relative to the directory containing the
+    /// file.
+    pub fn from_file<T, P>(path: P) -> Result<T>
+    where
+        T: Deserialize,
+        P: AsRef<Path>,
+    {
+        crate::options().file(path).parse()
+    }
+
+    /// Deserialize an instance of type `T` from a file of Dhall text,
+    /// additionally checking that it matches the supplied type.
+    ///
+    /// Like [from_file], but this additionally checks that
+    /// the type of the provided expression matches the supplied type.
+    pub fn from_file_check_type<T, P>(path: P, ty: &Value) -> Result<T>
+    where
+        T: Deserialize,
+        P: AsRef<Path>,
+    {
+        crate::options().file(path).type_annotation(ty).parse()
+    }
+
+    /// Deserialize an instance of type `T` from a file of Dhall text,
+    /// additionally checking that it matches the type of `T`.
+    ///
+    /// Like [from_file], but this additionally checks that
+    /// the type of the provided expression matches the output type `T`. The [StaticType] trait
+    /// captures Rust types that are valid Dhall types.
+    pub fn from_file_auto_type<T, P>(path: P) -> Result<T>
+    where
+        T: Deserialize + StaticType,
+        P: AsRef<Path>,
+    {
+        crate::options().file(path).parse_auto_type()
+    }
+
+    /// Deserialize an instance of type `T` from Dhall text read from `reader`.
+    ///
+    /// Like [from_str], relative imports are resolved relative to the current directory; use
+    /// [from_file] when the text comes from a file.
+    pub fn from_reader<T, R>(mut reader: R) -> Result<T>
+    where
+        T: Deserialize,
+        R: Read,
+    {
+        let mut s = String::new();
+        reader
+            .read_to_string(&mut s)
+            .map_err(|err| Error::Dhall(err.into()))?;
+        from_str(&s)
+    }
+
+    /// Deserialize an instance of type `T` from the binary encoding of a Dhall expression, e.g.
+    /// the contents of a `.dhallb` file.
+    ///
+    /// Like [from_str], relative imports are resolved relative to the current directory.
+    pub fn from_binary<T>(data: &[u8]) -> Result<T>
+    where
+        T: Deserialize,
+    {
+        crate::options().binary(data).parse()
+    }
+}
+
+/// Serialize Rust data structures to Dhall text.
+pub mod ser {
+    pub use super::de::{Error, Result};
+    use super::StaticType;
+
+    /// Serialize an instance of type `T` to a string of Dhall text.
+    ///
+    /// This fails if the value contains an empty list or a `None` whose type cannot be deduced
+    /// from the rest of the value; use [to_string_auto_type] for those.
+    pub fn to_string<T>(value: &T) -> Result<String>
+    where
+        T: serde::Serialize + ?Sized,
+    {
+        Ok(crate::serialize::to_expr(value, None)?.to_string())
+    }
+
+    /// Serialize an instance of type `T` to a string of Dhall text, using the type of `T` to
+    /// annotate empty lists and `None`s.
+    ///
+    /// Like [to_string], but the [StaticType] of `T` also gives enum values their full union
+    /// type.
+    pub fn to_string_auto_type<T>(value: &T) -> Result<String>
+    where
+        T: serde::Serialize + StaticType + ?Sized,
+    {
+        let ty = <T as StaticType>::static_type().to_expr();
+        Ok(crate::serialize::to_expr(value, Some(&ty))?.to_string())
     }
 }
@@ -0,0 +1,191 @@
+use std::path::{Path, PathBuf};
+
+use dhall::semantics::resolve::ImportResolver;
+use dhall::Parsed;
+
+use crate::de::{Deserialize, Error, Result};
+use crate::{StaticType, Value};
+
+#[derive(Debug, Clone)]
+enum Source<'a> {
+    Str(&'a str),
+    File(PathBuf),
+    Binary(&'a [u8]),
+}
+
+/// Controls how Dhall data is read, resolved and checked before being deserialized.
+///
+/// Build one by picking a source on [options](fn@crate::options) with [string](Options::string),
+/// [file](Options::file) or [binary](Options::binary), and finish with
+/// [parse](Deserializer::parse) or [parse_auto_type](Deserializer::parse_auto_type).
+///
+/// By default, record fields that the Rust type doesn't have are ignored, and
+/// [parse](Deserializer::parse) accepts data of any type that fits `T`;
+/// [strict](Deserializer::strict) turns both into errors.
+///
+/// ```rust
+/// # fn main() -> serde_dhall::de::Result<()> {
+/// use serde_dhall::StaticType;
+///
+/// let ty = <Vec<u64>>::static_type();
+/// let data = serde_dhall::options()
+///     .string("[1, 2]")
+///     .type_annotation(&ty)
+///     .imports(false)
+///     .parse::<Vec<u64>>()?;
+/// assert_eq!(data, vec![1, 2]);
+///
+/// // Strict mode needs a type annotation
+/// assert!(serde_dhall::options()
+///     .string("[1, 2]")
+///     .strict(true)
+///     .parse::<Vec<u64>>()
+///     .is_err());
+///
+/// // Imports are rejected
+/// let data = "./config.dhall";
+/// assert!(serde_dhall::options()
+///     .string(data)
+///     .imports(false)
+///     .parse::<Vec<u64>>()
+///     .is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Deserializer<'a> {
+    source: Source<'a>,
+    annot: Option<&'a Value>,
+    imports: bool,
+    resolver: Option<&'a ImportResolver>,
+    strict: bool,
+}
+
+/// Picks where the Dhall data of a [Deserializer] comes from. See [options](fn@crate::options).
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    _private: (),
+}
+
+/// Start configuring how to deserialize some Dhall data. See [Deserializer].
+pub fn options() -> Options {
+    Options { _private: () }
+}
+
+impl Options {
+    /// Read Dhall text from a string. Relative imports are resolved relative to the current
+    /// directory.
+    pub fn string(self, s: &str) -> Deserializer<'_> {
+        Deserializer::new(Source::Str(s))
+    }
+
+    /// Read Dhall text from a file. Relative imports are resolved relative to the directory
+    /// containing the file.
+    pub fn file<'a>(self, path: impl AsRef<Path>) -> Deserializer<'a> {
+        Deserializer::new(Source::File(path.as_ref().to_owned()))
+    }
+
+    /// Read the binary encoding of a Dhall expression, e.g. the contents of a `.dhallb` file.
+    /// Relative imports are resolved relative to the current directory.
+    pub fn binary(self, data: &[u8]) -> Deserializer<'_> {
+        Deserializer::new(Source::Binary(data))
+    }
+}
+
+impl<'a> Deserializer<'a> {
+    fn new(source: Source<'a>) -> Self {
+        Deserializer {
+            source,
+            annot: None,
+            imports: true,
+            resolver: None,
+            strict: false,
+        }
+    }
+
+    /// Check that the expression has type `ty` before deserializing it.
+    pub fn type_annotation(mut self, ty: &'a Value) -> Self {
+        self.annot = Some(ty);
+        self
+    }
+
+    /// Whether to resolve imports. If `false`, any import is an error. Defaults to `true`.
+    pub fn imports(mut self, imports: bool) -> Self {
+        self.imports = imports;
+        self
+    }
+
+    /// Resolve imports with `resolver` instead of the default [ImportResolver].
+    pub fn resolver(mut self, resolver: &'a ImportResolver) -> Self {
+        self.resolver = Some(resolver);
+        self
+    }
+
+    /// Whether to reject record fields that the Rust type doesn't have, and require a
+    /// [type_annotation](Deserializer::type_annotation) for [parse](Deserializer::parse).
+    /// Defaults to `false`.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Read, resolve and typecheck the Dhall data, then deserialize it into a `T`.
+    pub fn parse<T>(&self) -> Result<T>
+    where
+        T: Deserialize,
+    {
+        if self.strict && self.annot.is_none() {
+            return Err(Error::Deserialize(
+                "a type annotation is required in strict mode; use \
+                 `type_annotation` or `parse_auto_type`"
+                    .to_owned(),
+            ));
+        }
+        self.deserialize(&self.load(self.annot)?)
+    }
+
+    /// Like [parse](Deserializer::parse), but the expression must have the type of `T` instead of
+    /// the type given to [type_annotation](Deserializer::type_annotation). The [StaticType] trait
+    /// captures Rust types that are valid Dhall types.
+    pub fn parse_auto_type<T>(&self) -> Result<T>
+    where
+        T: Deserialize + StaticType,
+    {
+        self.deserialize(&self.load(Some(&T::static_type()))?)
+    }
+
+    fn deserialize<T>(&self, value: &Value) -> Result<T>
+    where
+        T: Deserialize,
+    {
+        if self.strict {
+            T::from_dhall_strict(value)
+        } else {
+            T::from_dhall(value)
+        }
+    }
+
+    fn load(&self, ty: Option<&Value>) -> Result<Value> {
+        self.load_using_dhall_error_type(ty).map_err(Error::Dhall)
+    }
+
+    fn load_using_dhall_error_type(
+        &self,
+        ty: Option<&Value>,
+    ) -> dhall::error::Result<Value> {
+        let parsed = match &self.source {
+            Source::Str(s) => Parsed::parse_str(s)?,
+            Source::File(path) => Parsed::parse_file(path)?,
+            Source::Binary(data) => Parsed::parse_binary(data)?,
+        };
+        if !self.imports {
+            return Value::from_parsed(parsed, ty, None);
+        }
+        match self.resolver {
+            Some(resolver) => Value::from_parsed(parsed, ty, Some(resolver)),
+            None => {
+                Value::from_parsed(parsed, ty, Some(&ImportResolver::new()))
+            }
+        }
+    }
+}
@@ -4,7 +4,7 @@ use serde::de::value::{
     MapAccessDeserializer, MapDeserializer, SeqDeserializer,
 };
 
-use dhall::syntax::ExprKind;
+use dhall::syntax::{ExprKind, Label};
 use dhall::NormalizedExpr;
 
 use crate::de::{Deserialize, Error, Result};
@@ -17,11 +17,43 @@ where
     T: serde::Deserialize<'a>,
 {
     fn from_dhall(v: &Value) -> Result<Self> {
-        T::deserialize(Deserializer(Cow::Owned(v.to_expr())))
+        T::deserialize(Deserializer::new(Cow::Owned(v.to_expr())))
     }
+
+    fn from_dhall_strict(v: &Value) -> Result<Self> {
+        T::deserialize(Deserializer {
+            strict: true,
+            ..Deserializer::new(Cow::Owned(v.to_expr()))
+        })
+    }
+}
+
+struct Deserializer<'a> {
+    expr: Cow<'a, NormalizedExpr>,
+    /// Whether record fields that the Rust type ignores are an error.
+    strict: bool,
+    /// The label of the record field holding `expr`, if any.
+    field: Option<&'a str>,
 }
 
-struct Deserializer<'a>(Cow<'a, NormalizedExpr>);
+impl<'a> Deserializer<'a> {
+    fn new(expr: Cow<'a, NormalizedExpr>) -> Self {
+        Deserializer {
+            expr,
+            strict: false,
+            field: None,
+        }
+    }
+
+    /// A deserializer for a subexpression, with the same settings.
+    fn at<'b>(&self, expr: &'b NormalizedExpr) -> Deserializer<'b> {
+        Deserializer {
+            expr: Cow::Borrowed(expr),
+            strict: self.strict,
+            field: None,
+        }
+    }
+}
 
 impl<'de: 'a, 'a> serde::de::IntoDeserializer<'de, Error> for Deserializer<'a> {
     type Deserializer = Deserializer<'a>;
@@ -39,7 +71,7 @@ impl<'de: 'a, 'a> serde::Deserializer<'de> for Deserializer<'a> {
     {
         use std::convert::TryInto;
         use ExprKind::*;
-        let expr = self.0.as_ref();
+        let expr = self.expr.as_ref();
         let not_serde_compatible = || {
             Err(Error::Deserialize(format!(
                 "this cannot be deserialized into the serde data model: {}",
@@ -68,6 +100,12 @@ impl<'de: 'a, 'a> serde::Deserializer<'de> for Deserializer<'a> {
                 }
             }
             DoubleLit(x) => visitor.visit_f64((*x).into()),
+            DateLit(x) => visitor.visit_string(x.to_string()),
+            TimeLit(x) => visitor.visit_string(x.to_string()),
+            TimeZoneLit(x) => visitor.visit_string(x.to_string()),
+            BytesLit(x) => {
+                visitor.visit_seq(SeqDeserializer::new(x.iter().cloned()))
+            }
             TextLit(x) => {
                 // Normal form ensures that the tail is empty.
                 assert!(x.tail().is_empty());
@@ -76,10 +114,9 @@ impl<'de: 'a, 'a> serde::Deserializer<'de> for Deserializer<'a> {
             EmptyListLit(..) => {
                 visitor.visit_seq(SeqDeserializer::new(None::<()>.into_iter()))
             }
-            NEListLit(xs) => visitor.visit_seq(SeqDeserializer::new(
-                xs.iter().map(|x| Deserializer(Cow::Borrowed(x))),
-            )),
-            SomeLit(x) => visitor.visit_some(Deserializer(Cow::Borrowed(x))),
+            NEListLit(xs) => visitor
+                .visit_seq(SeqDeserializer::new(xs.iter().map(|x| self.at(x)))),
+            SomeLit(x) => visitor.visit_some(self.at(x)),
             App(f, x) => match f.as_ref() {
                 Builtin(dhall::syntax::Builtin::OptionalNone) => {
                     visitor.visit_none()
@@ -89,8 +126,7 @@ impl<'de: 'a, 'a> serde::Deserializer<'de> for Deserializer<'a> {
                         let name: String = name.into();
                         visitor.visit_enum(MapAccessDeserializer::new(
                             MapDeserializer::new(
-                                Some((name, Deserializer(Cow::Borrowed(x))))
-                                    .into_iter(),
+                                Some((name, self.at(x))).into_iter(),
                             ),
                         ))
                     }
@@ -98,10 +134,18 @@ impl<'de: 'a, 'a> serde::Deserializer<'de> for Deserializer<'a> {
                 },
                 _ => not_serde_compatible(),
             },
-            RecordLit(m) => visitor
-                .visit_map(MapDeserializer::new(m.iter().map(|(k, v)| {
-                    (k.as_ref(), Deserializer(Cow::Borrowed(v)))
-                }))),
+            RecordLit(m) => visitor.visit_map(MapDeserializer::new(
+                m.iter().map(|(k, v)| {
+                    let field = Some(k.as_ref());
+                    (
+                        k.as_ref(),
+                        Deserializer {
+                            field,
+                            ..self.at(v)
+                        },
+                    )
+                }),
+            )),
             Field(y, name) => match y.as_ref() {
                 UnionType(..) => {
                     let name: String = name.into();
@@ -111,11 +155,15 @@ impl<'de: 'a, 'a> serde::Deserializer<'de> for Deserializer<'a> {
                 }
                 _ => not_serde_compatible(),
             },
-            Const(..) | Var(..) | Lam(..) | Pi(..) | Let(..) | Annot(..)
-            | Assert(..) | Builtin(..) | BinOp(..) | BoolIf(..)
-            | RecordType(..) | UnionType(..) | Merge(..) | ToMap(..)
+            Lam(..) => match json_value(expr) {
+                Some(json) => json.deserialize_any(visitor),
+                None => not_serde_compatible(),
+            },
+            Const(..) | Var(..) | Pi(..) | Let(..) | Annot(..) | Assert(..)
+            | Builtin(..) | BinOp(..) | BoolIf(..) | RecordType(..)
+            | UnionType(..) | Merge(..) | ToMap(..) | ShowConstructor(..)
             | Projection(..) | ProjectionByExpr(..) | Completion(..)
-            | Import(..) | Embed(..) => not_serde_compatible(),
+            | With(..) | Import(..) | Embed(..) => not_serde_compatible(),
         }
     }
 
@@ -124,20 +172,254 @@ impl<'de: 'a, 'a> serde::Deserializer<'de> for Deserializer<'a> {
         V: serde::de::Visitor<'de>,
     {
         use ExprKind::*;
-        let expr = self.0.as_ref();
+        let expr = self.expr.as_ref();
 
         match expr.as_ref() {
             // Blindly takes keys in sorted order.
             RecordLit(m) => visitor.visit_seq(SeqDeserializer::new(
-                m.iter().map(|(_, v)| Deserializer(Cow::Borrowed(v))),
+                m.iter().map(|(_, v)| self.at(v)),
             )),
             _ => self.deserialize_any(visitor),
         }
     }
 
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        use ExprKind::*;
+        let expr = self.expr.as_ref();
+
+        // Lists of type `Prelude.Map.Type`, i.e. `List { mapKey : K, mapValue : V }`.
+        match expr.as_ref() {
+            EmptyListLit(ty) if is_map_type(ty) => visitor
+                .visit_map(MapDeserializer::new(None::<((), ())>.into_iter())),
+            NEListLit(xs) => {
+                match xs.iter().map(map_entry).collect::<Option<Vec<_>>>() {
+                    Some(entries) => visitor.visit_map(MapDeserializer::new(
+                        entries
+                            .into_iter()
+                            .map(|(k, v)| (self.at(k), self.at(v))),
+                    )),
+                    None => self.deserialize_any(visitor),
+                }
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        match self.expr.as_ref().as_ref() {
+            ExprKind::BytesLit(x) => visitor.visit_bytes(x),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    /// Called on the record fields that the Rust type doesn't have.
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        match self.field {
+            Some(field) if self.strict => {
+                Err(Error::Deserialize(format!("unknown field `{}`", field)))
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        option unit unit_struct newtype_struct seq
+        tuple_struct struct enum identifier
+    }
+}
+
+/// Whether `ty` is `List { mapKey : K, mapValue : V }`.
+fn is_map_type(ty: &NormalizedExpr) -> bool {
+    use ExprKind::*;
+    match ty.as_ref() {
+        App(f, x) => match (f.as_ref(), x.as_ref()) {
+            (Builtin(dhall::syntax::Builtin::List), RecordType(kts)) => {
+                let mut keys = kts.iter().map(|(k, _)| k.as_ref());
+                keys.next() == Some("mapKey")
+                    && keys.next() == Some("mapValue")
+                    && keys.next() == None
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// The key and value of a `{ mapKey = k, mapValue = v }` record.
+fn map_entry(x: &NormalizedExpr) -> Option<(&NormalizedExpr, &NormalizedExpr)> {
+    match x.as_ref() {
+        ExprKind::RecordLit(m) if m.len() == 2 => {
+            let mut entries = m.iter();
+            match (entries.next()?, entries.next()?) {
+                ((k, key), (v, value))
+                    if k.as_ref() == "mapKey" && v.as_ref() == "mapValue" =>
+                {
+                    Some((key, value))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The handlers of `Prelude.JSON.Type`, across its versions.
+const JSON_HANDLERS: &[&str] = &[
+    "array", "bool", "double", "integer", "null", "number", "object", "string",
+];
+
+/// If `expr` is a `Prelude.JSON.Type` value, i.e.
+/// `λ(JSON : Type) → λ(json : { array : List JSON → JSON, ... }) → body`, a deserializer for its
+/// body.
+fn json_value(expr: &NormalizedExpr) -> Option<JsonDeserializer<'_>> {
+    use ExprKind::*;
+    match expr.as_ref() {
+        Lam(_, ty, body) => match (ty.as_ref(), body.as_ref()) {
+            (
+                Const(dhall::syntax::Const::Type),
+                Lam(handlers, handlers_ty, body),
+            ) => match handlers_ty.as_ref() {
+                RecordType(kts)
+                    if !kts.is_empty()
+                        && kts.iter().all(|(k, _)| {
+                            JSON_HANDLERS.contains(&k.as_ref())
+                        }) =>
+                {
+                    Some(JsonDeserializer {
+                        expr: body,
+                        handlers,
+                    })
+                }
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Deserializes the body of a `Prelude.JSON.Type` value, by interpreting the calls to the
+/// handlers it is made of.
+#[derive(Clone, Copy)]
+struct JsonDeserializer<'a> {
+    expr: &'a NormalizedExpr,
+    /// The name of the record of handlers.
+    handlers: &'a Label,
+}
+
+impl<'a> JsonDeserializer<'a> {
+    fn at(self, expr: &'a NormalizedExpr) -> Self {
+        JsonDeserializer { expr, ..self }
+    }
+
+    /// The handler applied at the root of the expression, with its argument if any.
+    fn handler(self) -> Option<(&'a str, Option<&'a NormalizedExpr>)> {
+        use ExprKind::*;
+        let handler_name = |e: &'a NormalizedExpr| match e.as_ref() {
+            Field(r, name) => match r.as_ref() {
+                Var(dhall::syntax::V(l, 0)) if l == self.handlers => {
+                    Some(name.as_ref())
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        match self.expr.as_ref() {
+            App(f, x) => Some((handler_name(f)?, Some(x))),
+            _ => Some((handler_name(self.expr)?, None)),
+        }
+    }
+}
+
+impl<'de: 'a, 'a> serde::de::IntoDeserializer<'de, Error>
+    for JsonDeserializer<'a>
+{
+    type Deserializer = JsonDeserializer<'a>;
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+impl<'de: 'a, 'a> serde::Deserializer<'de> for JsonDeserializer<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        use ExprKind::*;
+        let not_json = || {
+            Err(Error::Deserialize(format!(
+                "this is not a valid `Prelude.JSON.Type` value: {}",
+                self.expr
+            )))
+        };
+
+        let (handler, arg) = match self.handler() {
+            Some((handler, arg)) => (handler, arg.map(|x| x.as_ref())),
+            None => return not_json(),
+        };
+        match (handler, arg) {
+            ("null", None) => visitor.visit_unit(),
+            ("bool", Some(BoolLit(x))) => visitor.visit_bool(*x),
+            ("double", Some(DoubleLit(x))) | ("number", Some(DoubleLit(x))) => {
+                visitor.visit_f64((*x).into())
+            }
+            ("integer", Some(IntegerLit(x))) => visitor.visit_i64(*x as i64),
+            ("string", Some(TextLit(x))) if x.tail().is_empty() => {
+                visitor.visit_str(x.head())
+            }
+            ("array", Some(EmptyListLit(..))) => {
+                visitor.visit_seq(SeqDeserializer::new(None::<()>.into_iter()))
+            }
+            ("array", Some(NEListLit(xs))) => visitor
+                .visit_seq(SeqDeserializer::new(xs.iter().map(|x| self.at(x)))),
+            ("object", Some(EmptyListLit(..))) => visitor
+                .visit_map(MapDeserializer::new(None::<((), ())>.into_iter())),
+            ("object", Some(NEListLit(xs))) => {
+                match xs.iter().map(map_entry).collect::<Option<Vec<_>>>() {
+                    Some(entries) => visitor.visit_map(MapDeserializer::new(
+                        entries.into_iter().map(|(k, v)| {
+                            (Deserializer::new(Cow::Borrowed(k)), self.at(v))
+                        }),
+                    )),
+                    None => not_json(),
+                }
+            }
+            _ => not_json(),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        match self.handler() {
+            Some(("null", None)) => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
     serde::forward_to_deserialize_any! {
         bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
-        bytes byte_buf option unit unit_struct newtype_struct seq
+        bytes byte_buf unit unit_struct newtype_struct seq tuple
         tuple_struct map struct enum identifier ignored_any
     }
 }
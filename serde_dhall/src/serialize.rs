@@ -0,0 +1,546 @@
+use serde::ser::{self, Serialize};
+
+use dhall::syntax::map::DupTreeMap;
+use dhall::syntax::{Builtin, Expr, ExprKind, Label, Span};
+use dhall::{Normalized, NormalizedExpr};
+
+use crate::ser::{Error, Result};
+
+/// Serialize a value to a Dhall expression. `ty` is the Dhall type of the value, if known. It is
+/// used to annotate empty lists and `None`s, and to give enum values their full union type.
+pub(crate) fn to_expr<T>(
+    value: &T,
+    ty: Option<&NormalizedExpr>,
+) -> Result<NormalizedExpr>
+where
+    T: Serialize + ?Sized,
+{
+    value.serialize(Serializer { ty })
+}
+
+fn mk(kind: ExprKind<NormalizedExpr, Normalized>) -> NormalizedExpr {
+    Expr::new(kind, Span::Artificial)
+}
+
+fn mk_builtin_app(b: Builtin, arg: NormalizedExpr) -> NormalizedExpr {
+    mk(ExprKind::App(mk(ExprKind::Builtin(b)), arg))
+}
+
+fn missing_type(what: &str) -> Error {
+    Error::Serialize(format!(
+        "cannot serialize {} without knowing its type; use \
+         `to_string_auto_type`",
+        what
+    ))
+}
+
+/// The argument `T` of the type `b T`, e.g. the element type of a list.
+fn builtin_arg(
+    ty: Option<&NormalizedExpr>,
+    b: Builtin,
+) -> Option<&NormalizedExpr> {
+    match ty?.as_ref() {
+        ExprKind::App(f, arg) => match f.as_ref() {
+            ExprKind::Builtin(b2) if *b2 == b => Some(arg),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The type of the field `label` of a record type.
+fn field_type<'a>(
+    ty: Option<&'a NormalizedExpr>,
+    label: &Label,
+) -> Option<&'a NormalizedExpr> {
+    match ty?.as_ref() {
+        ExprKind::RecordType(kts) => {
+            kts.iter().find(|(k, _)| *k == label).map(|(_, t)| t)
+        }
+        _ => None,
+    }
+}
+
+/// The type of the contents of the alternative `label` of a union type.
+fn alternative_type<'a>(
+    ty: Option<&'a NormalizedExpr>,
+    label: &Label,
+) -> Option<&'a NormalizedExpr> {
+    match ty?.as_ref() {
+        ExprKind::UnionType(kts) => kts
+            .iter()
+            .find(|(k, _)| *k == label)
+            .and_then(|(_, t)| t.as_ref()),
+        _ => None,
+    }
+}
+
+/// The type of a serialized value, when it can be read off its syntax.
+fn type_of(expr: &NormalizedExpr) -> Option<NormalizedExpr> {
+    let builtin = |b| Some(mk(ExprKind::Builtin(b)));
+    match expr.as_ref() {
+        ExprKind::BoolLit(_) => builtin(Builtin::Bool),
+        ExprKind::NaturalLit(_) => builtin(Builtin::Natural),
+        ExprKind::IntegerLit(_) => builtin(Builtin::Integer),
+        ExprKind::DoubleLit(_) => builtin(Builtin::Double),
+        ExprKind::TextLit(_) => builtin(Builtin::Text),
+        ExprKind::BytesLit(_) => builtin(Builtin::Bytes),
+        ExprKind::EmptyListLit(t) => Some(t.clone()),
+        ExprKind::NEListLit(xs) => {
+            Some(mk_builtin_app(Builtin::List, type_of(&xs[0])?))
+        }
+        ExprKind::SomeLit(x) => {
+            Some(mk_builtin_app(Builtin::Optional, type_of(x)?))
+        }
+        ExprKind::RecordLit(kvs) => {
+            let kts = kvs
+                .iter()
+                .map(|(k, v)| Some((k.clone(), type_of(v)?)))
+                .collect::<Option<_>>()?;
+            Some(mk(ExprKind::RecordType(kts)))
+        }
+        ExprKind::Field(union, _) => Some(union.clone()),
+        ExprKind::App(f, x) => match f.as_ref() {
+            ExprKind::Builtin(Builtin::OptionalNone) => {
+                Some(mk_builtin_app(Builtin::Optional, x.clone()))
+            }
+            ExprKind::Field(union, _) => Some(union.clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The value `variant` of the union type `ty`, holding `contents` if any. If the union type is not
+/// known, use one with the single alternative `variant`.
+fn make_variant(
+    ty: Option<&NormalizedExpr>,
+    variant: &str,
+    contents: Option<NormalizedExpr>,
+) -> Result<NormalizedExpr> {
+    let union = match ty.map(|ty| (ty, ty.as_ref())) {
+        Some((ty, ExprKind::UnionType(kts))) => {
+            let label: Label = variant.into();
+            let fits = kts
+                .iter()
+                .any(|(k, t)| *k == label && t.is_some() == contents.is_some());
+            if !fits {
+                return Err(Error::Serialize(format!(
+                    "the enum variant `{}` does not fit the union type `{}` \
+                     guessed from an earlier value; use `to_string_auto_type` \
+                     to annotate the value with its full type",
+                    variant, ty
+                )));
+            }
+            ty.clone()
+        }
+        _ => {
+            let contents_type = match &contents {
+                Some(x) => Some(
+                    type_of(x)
+                        .ok_or_else(|| missing_type("this enum value"))?,
+                ),
+                None => None,
+            };
+            let mut kts = DupTreeMap::new();
+            kts.insert(variant.into(), contents_type);
+            mk(ExprKind::UnionType(kts))
+        }
+    };
+    let constructor = mk(ExprKind::Field(union, variant.into()));
+    Ok(match contents {
+        Some(x) => mk(ExprKind::App(constructor, x)),
+        None => constructor,
+    })
+}
+
+#[derive(Clone, Copy)]
+struct Serializer<'a> {
+    /// The expected type of the value, if known.
+    ty: Option<&'a NormalizedExpr>,
+}
+
+/// Serializes sequences to lists.
+struct SeqSerializer<'a> {
+    ty: Option<&'a NormalizedExpr>,
+    elems: Vec<NormalizedExpr>,
+    /// The type of the first element, used for the following ones if the list type is unknown.
+    elem_type: Option<NormalizedExpr>,
+}
+
+/// Serializes tuples, structs and maps to records, optionally wrapped in an enum variant.
+struct RecordSerializer<'a> {
+    /// The expected record type, if known.
+    ty: Option<&'a NormalizedExpr>,
+    /// For enum variants: the name of the variant and the expected union type.
+    variant: Option<(&'static str, Option<&'a NormalizedExpr>)>,
+    fields: DupTreeMap<Label, NormalizedExpr>,
+    /// The key of the map entry being serialized.
+    key: Option<Label>,
+    /// The type of the first map value, used for the following ones if the record type is
+    /// unknown.
+    value_type: Option<NormalizedExpr>,
+}
+
+impl<'a> RecordSerializer<'a> {
+    fn new(
+        ty: Option<&'a NormalizedExpr>,
+        variant: Option<(&'static str, Option<&'a NormalizedExpr>)>,
+    ) -> Self {
+        RecordSerializer {
+            ty,
+            variant,
+            fields: DupTreeMap::new(),
+            key: None,
+            value_type: None,
+        }
+    }
+
+    fn field<T>(&mut self, label: Label, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        let ty = field_type(self.ty, &label);
+        self.fields.insert(label, to_expr(value, ty)?);
+        Ok(())
+    }
+
+    /// Unlike struct fields, map values all have the same type, so the first one tells the type of
+    /// the others.
+    fn map_value<T>(&mut self, label: Label, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        let ty =
+            field_type(self.ty, &label).or_else(|| self.value_type.as_ref());
+        let value = to_expr(value, ty)?;
+        if self.value_type.is_none() {
+            self.value_type = type_of(&value);
+        }
+        self.fields.insert(label, value);
+        Ok(())
+    }
+
+    fn tuple_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        let label = Label::from(format!("_{}", self.fields.len() + 1));
+        let ty = field_type(self.ty, &label);
+        self.fields.insert(label, to_expr(value, ty)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<NormalizedExpr> {
+        let record = mk(ExprKind::RecordLit(self.fields));
+        match self.variant {
+            Some((variant, ty)) => make_variant(ty, variant, Some(record)),
+            None => Ok(record),
+        }
+    }
+}
+
+impl<'a> ser::Serializer for Serializer<'a> {
+    type Ok = NormalizedExpr;
+    type Error = Error;
+    type SerializeSeq = SeqSerializer<'a>;
+    type SerializeTuple = RecordSerializer<'a>;
+    type SerializeTupleStruct = RecordSerializer<'a>;
+    type SerializeTupleVariant = RecordSerializer<'a>;
+    type SerializeMap = RecordSerializer<'a>;
+    type SerializeStruct = RecordSerializer<'a>;
+    type SerializeStructVariant = RecordSerializer<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok> {
+        Ok(mk(ExprKind::BoolLit(v)))
+    }
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok> {
+        self.serialize_i64(v.into())
+    }
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok> {
+        self.serialize_i64(v.into())
+    }
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok> {
+        self.serialize_i64(v.into())
+    }
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok> {
+        Ok(mk(ExprKind::IntegerLit(v as isize)))
+    }
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok> {
+        self.serialize_u64(v.into())
+    }
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok> {
+        self.serialize_u64(v.into())
+    }
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok> {
+        self.serialize_u64(v.into())
+    }
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok> {
+        Ok(mk(ExprKind::NaturalLit(v as usize)))
+    }
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok> {
+        self.serialize_f64(v.into())
+    }
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok> {
+        Ok(mk(ExprKind::DoubleLit(v.into())))
+    }
+    fn serialize_char(self, v: char) -> Result<Self::Ok> {
+        self.serialize_str(&v.to_string())
+    }
+    fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+        Ok(mk(ExprKind::TextLit(v.to_string().into())))
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
+        Ok(mk(ExprKind::BytesLit(v.to_vec())))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        match builtin_arg(self.ty, Builtin::Optional) {
+            Some(t) => Ok(mk_builtin_app(Builtin::OptionalNone, t.clone())),
+            None => Err(missing_type("`None`")),
+        }
+    }
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok>
+    where
+        T: Serialize + ?Sized,
+    {
+        let ty = builtin_arg(self.ty, Builtin::Optional);
+        Ok(mk(ExprKind::SomeLit(to_expr(value, ty)?)))
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        Ok(mk(ExprKind::RecordLit(DupTreeMap::new())))
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok> {
+        self.serialize_unit()
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok> {
+        make_variant(self.ty, variant, None)
+    }
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok>
+    where
+        T: Serialize + ?Sized,
+    {
+        let ty = alternative_type(self.ty, &variant.into());
+        make_variant(self.ty, variant, Some(to_expr(value, ty)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(SeqSerializer {
+            ty: self.ty,
+            elems: Vec::with_capacity(len.unwrap_or(0)),
+            elem_type: None,
+        })
+    }
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Ok(RecordSerializer::new(self.ty, None))
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Ok(RecordSerializer::new(self.ty, None))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        let ty = alternative_type(self.ty, &variant.into());
+        Ok(RecordSerializer::new(ty, Some((variant, self.ty))))
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(RecordSerializer::new(self.ty, None))
+    }
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct> {
+        Ok(RecordSerializer::new(self.ty, None))
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        let ty = alternative_type(self.ty, &variant.into());
+        Ok(RecordSerializer::new(ty, Some((variant, self.ty))))
+    }
+}
+
+impl<'a> ser::SerializeSeq for SeqSerializer<'a> {
+    type Ok = NormalizedExpr;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        let ty = builtin_arg(self.ty, Builtin::List)
+            .or_else(|| self.elem_type.as_ref());
+        let value = to_expr(value, ty)?;
+        if self.elem_type.is_none() {
+            self.elem_type = type_of(&value);
+        }
+        self.elems.push(value);
+        Ok(())
+    }
+    fn end(self) -> Result<Self::Ok> {
+        if !self.elems.is_empty() {
+            return Ok(mk(ExprKind::NEListLit(self.elems)));
+        }
+        match self.ty {
+            Some(ty) if builtin_arg(Some(ty), Builtin::List).is_some() => {
+                Ok(mk(ExprKind::EmptyListLit(ty.clone())))
+            }
+            _ => Err(missing_type("an empty list")),
+        }
+    }
+}
+
+impl<'a> ser::SerializeTuple for RecordSerializer<'a> {
+    type Ok = NormalizedExpr;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.tuple_field(value)
+    }
+    fn end(self) -> Result<Self::Ok> {
+        RecordSerializer::end(self)
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for RecordSerializer<'a> {
+    type Ok = NormalizedExpr;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.tuple_field(value)
+    }
+    fn end(self) -> Result<Self::Ok> {
+        RecordSerializer::end(self)
+    }
+}
+
+impl<'a> ser::SerializeTupleVariant for RecordSerializer<'a> {
+    type Ok = NormalizedExpr;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.tuple_field(value)
+    }
+    fn end(self) -> Result<Self::Ok> {
+        RecordSerializer::end(self)
+    }
+}
+
+impl<'a> ser::SerializeMap for RecordSerializer<'a> {
+    type Ok = NormalizedExpr;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = to_expr(key, None)?;
+        match key.as_ref() {
+            ExprKind::TextLit(text) if text.tail().is_empty() => {
+                let key = text.head();
+                // Keys are printed as (possibly quoted) labels
+                let printable = !key.is_empty()
+                    && key.chars().all(|c| matches!(c, ' '..='_' | 'a'..='~'));
+                if !printable {
+                    return Err(Error::Serialize(format!(
+                        "map key {:?} cannot be used as a record field",
+                        key
+                    )));
+                }
+                self.key = Some(key.into());
+                Ok(())
+            }
+            _ => Err(Error::Serialize(format!(
+                "map keys must be strings, found: {}",
+                key
+            ))),
+        }
+    }
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value called before serialize_key");
+        self.map_value(key, value)
+    }
+    fn end(self) -> Result<Self::Ok> {
+        RecordSerializer::end(self)
+    }
+}
+
+impl<'a> ser::SerializeStruct for RecordSerializer<'a> {
+    type Ok = NormalizedExpr;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.field(key.into(), value)
+    }
+    fn end(self) -> Result<Self::Ok> {
+        RecordSerializer::end(self)
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for RecordSerializer<'a> {
+    type Ok = NormalizedExpr;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.field(key.into(), value)
+    }
+    fn end(self) -> Result<Self::Ok> {
+        RecordSerializer::end(self)
+    }
+}
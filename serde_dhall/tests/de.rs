@@ -21,7 +21,7 @@ fn test_de_typed() {
     assert_eq!(parse::<f32>("1.0"), 1.0);
 
     assert_eq!(parse::<String>(r#""foo""#), "foo".to_owned());
-    assert_eq!(parse::<Vec<u64>>("[] : List Natural"), vec![]);
+    assert_eq!(parse::<Vec<u64>>("[] : List Natural"), Vec::<u64>::new());
     assert_eq!(parse::<Vec<u64>>("[1, 2]"), vec![1, 2]);
     assert_eq!(parse::<Option<u64>>("None Natural"), None);
     assert_eq!(parse::<Option<u64>>("Some 1"), Some(1));
@@ -82,4 +82,265 @@ fn test_de_untyped() {
         parse::<BTreeMap<String, usize>>("{ x = 1, y = 2 }"),
         expected_map
     );
+
+    // `Prelude.Map.Type`
+    let mut expected_map = HashMap::new();
+    expected_map.insert("foo bar".to_string(), 1);
+    expected_map.insert("baz".to_string(), 2);
+    assert_eq!(
+        parse::<HashMap<String, usize>>(r#"toMap { `foo bar` = 1, baz = 2 }"#),
+        expected_map
+    );
+    let mut expected_map = BTreeMap::new();
+    expected_map.insert(1, "x".to_string());
+    assert_eq!(
+        parse::<BTreeMap<u64, String>>(r#"[{ mapKey = 1, mapValue = "x" }]"#),
+        expected_map
+    );
+    assert_eq!(
+        parse::<HashMap<String, usize>>(
+            "[] : List { mapKey : Text, mapValue : Natural }"
+        ),
+        HashMap::new()
+    );
+    assert!(from_str::<HashMap<String, usize>>("[] : List Natural").is_err());
+    assert!(from_str::<HashMap<String, usize>>("[1, 2]").is_err());
+
+    assert_eq!(parse::<Vec<u8>>(r#"0x"00fFa0""#), vec![0x00, 0xff, 0xa0]);
+    assert_eq!(
+        parse::<serde_bytes::ByteBuf>(r#"0x"DEADBEEF""#).into_vec(),
+        vec![0xde, 0xad, 0xbe, 0xef]
+    );
+
+    // Temporal literals are deserialized as strings
+    #[derive(Debug, PartialEq, Eq, Deserialize)]
+    struct Meeting {
+        date: String,
+        time: String,
+        #[serde(rename = "timeZone")]
+        time_zone: String,
+    }
+    assert_eq!(
+        parse::<Meeting>("2020-01-31T09:30:00.5+01:00"),
+        Meeting {
+            date: "2020-01-31".to_owned(),
+            time: "09:30:00.5".to_owned(),
+            time_zone: "+01:00".to_owned(),
+        }
+    );
+}
+
+#[test]
+fn test_de_file() {
+    use serde_dhall::{
+        from_file, from_file_auto_type, options, ImportResolver,
+    };
+
+    // Relative imports are resolved against the directory of the file, not the current directory
+    let dir = std::env::temp_dir()
+        .join(format!("serde_dhall_test_de_file_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("sub").join("common.dhall"), "{ x = 1 }").unwrap();
+    std::fs::write(
+        dir.join("sub").join("config.dhall"),
+        "./common.dhall // { y = 2 }",
+    )
+    .unwrap();
+    let path = dir.join("sub").join("config.dhall");
+
+    #[derive(Debug, PartialEq, Eq, Deserialize, StaticType)]
+    struct Foo {
+        x: u64,
+        y: u64,
+    }
+    let resolver = ImportResolver::new().no_cache();
+    let load = |path| options().file(path).resolver(&resolver);
+    assert_eq!(load(&path).parse::<Foo>().unwrap(), Foo { x: 1, y: 2 });
+    assert_eq!(
+        load(&path).parse_auto_type::<Foo>().unwrap(),
+        Foo { x: 1, y: 2 }
+    );
+    assert!(load(&path).parse_auto_type::<u64>().is_err());
+    assert!(load(&dir.join("missing.dhall")).parse::<u64>().is_err());
+    // Without hashed imports the shortcuts never read the cache
+    std::fs::write(dir.join("simple.dhall"), "{ x = 1, y = 2 }").unwrap();
+    let simple = dir.join("simple.dhall");
+    assert_eq!(from_file::<Foo, _>(&simple).unwrap(), Foo { x: 1, y: 2 });
+    assert_eq!(
+        from_file_auto_type::<Foo, _>(&simple).unwrap(),
+        Foo { x: 1, y: 2 }
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_de_reader() {
+    use serde_dhall::from_reader;
+
+    let data: &[u8] = b"[1, 2]";
+    assert_eq!(from_reader::<Vec<u64>, _>(data).unwrap(), vec![1, 2]);
+    let data: &[u8] = b"\xff";
+    assert!(from_reader::<Vec<u64>, _>(data).is_err());
+}
+
+#[test]
+fn test_de_binary() {
+    use serde_dhall::from_binary;
+
+    let data = dhall::Parsed::parse_str("[1, 2]")
+        .unwrap()
+        .encode()
+        .unwrap();
+    assert_eq!(from_binary::<Vec<u64>>(&data).unwrap(), vec![1, 2]);
+    assert!(from_binary::<Vec<u64>>(b"not cbor").is_err());
+}
+
+#[test]
+fn test_de_options() {
+    use serde_dhall::{options, ImportResolver, MemoryFetcher};
+
+    let ty = <Vec<u64>>::static_type();
+    let de = options().string("[1, 2]");
+    assert_eq!(de.parse::<Vec<u64>>().unwrap(), vec![1, 2]);
+    assert_eq!(
+        de.clone().type_annotation(&ty).parse::<Vec<u64>>().unwrap(),
+        vec![1, 2]
+    );
+    assert!(de
+        .type_annotation(&<Vec<i64>>::static_type())
+        .parse::<Vec<u64>>()
+        .is_err());
+    assert!(options().string("1").parse_auto_type::<String>().is_err());
+
+    let mut fetcher = MemoryFetcher::new();
+    fetcher.insert("https://example.com/x.dhall", "1");
+    let resolver = ImportResolver::new().fetcher(fetcher);
+    let de = options().string("https://example.com/x.dhall");
+    assert_eq!(de.clone().resolver(&resolver).parse::<u64>().unwrap(), 1);
+    assert!(de
+        .resolver(&resolver)
+        .imports(false)
+        .parse::<u64>()
+        .is_err());
+}
+
+#[test]
+fn test_de_strict() {
+    use serde_dhall::options;
+
+    #[derive(Debug, PartialEq, Eq, Deserialize, StaticType)]
+    struct Point {
+        x: u64,
+    }
+    // The type of the data, which has more fields than `Point`
+    #[derive(StaticType)]
+    #[allow(dead_code)]
+    struct Point2 {
+        x: u64,
+        y: u64,
+    }
+    #[derive(Debug, PartialEq, Eq, Deserialize)]
+    enum Shape {
+        Dot(Point),
+    }
+    #[derive(StaticType)]
+    #[allow(dead_code)]
+    enum Shape2 {
+        Dot(Point2),
+    }
+
+    let ty = Point2::static_type();
+    let de = options().string("{ x = 1, y = 2 }");
+    assert_eq!(de.parse::<Point>().unwrap(), Point { x: 1 });
+    let err = de.clone().strict(true).parse::<Point>().unwrap_err();
+    assert!(err.to_string().contains("type annotation"), "{}", err);
+    let err = de
+        .clone()
+        .strict(true)
+        .type_annotation(&ty)
+        .parse::<Point>()
+        .unwrap_err();
+    assert!(err.to_string().contains("unknown field `y`"), "{}", err);
+    // The type of `Point` itself doesn't allow the extra field
+    assert!(de.strict(true).parse_auto_type::<Point>().is_err());
+
+    // Nested records and enum payloads are checked too
+    let ty = <Vec<Point2>>::static_type();
+    let de = options()
+        .string("[{ x = 1, y = 2 }, { x = 3, y = 4 }]")
+        .type_annotation(&ty);
+    assert!(de.clone().parse::<Vec<Point>>().is_ok());
+    assert!(de.strict(true).parse::<Vec<Point>>().is_err());
+    let ty = Shape2::static_type();
+    let de = options()
+        .string("< Dot : { x : Natural, y : Natural } >.Dot { x = 1, y = 2 }")
+        .type_annotation(&ty);
+    assert_eq!(de.parse::<Shape>().unwrap(), Shape::Dot(Point { x: 1 }));
+    assert!(de.strict(true).parse::<Shape>().is_err());
+
+    // Data that fits exactly is accepted
+    let de = options().string("{ x = 1 }").strict(true);
+    assert_eq!(de.parse_auto_type::<Point>().unwrap(), Point { x: 1 });
+}
+
+#[test]
+fn test_de_json() {
+    use serde_json::json;
+
+    // The body of a `Prelude.JSON.Type` value
+    fn json(body: &str) -> String {
+        format!(
+            "λ(JSON : Type) → λ(json : {{ array : List JSON → JSON, bool : \
+             Bool → JSON, double : Double → JSON, integer : Integer → JSON, \
+             null : JSON, object : List {{ mapKey : Text, mapValue : JSON }} \
+             → JSON, string : Text → JSON }}) → {}",
+            body
+        )
+    }
+    fn parse<T: serde_dhall::de::Deserialize>(body: &str) -> T {
+        from_str(&json(body)).unwrap()
+    }
+
+    assert_eq!(parse::<serde_json::Value>("json.null"), json!(null));
+    assert_eq!(
+        parse::<serde_json::Value>(
+            r#"json.object (toMap {
+                 a = json.array [json.integer -1, json.double 1.5, json.bool True],
+                 `b c` = json.string "x",
+                 d = json.object ([] : List { mapKey : Text, mapValue : JSON })
+               })"#
+        ),
+        json!({ "a": [-1, 1.5, true], "b c": "x", "d": {} })
+    );
+
+    #[derive(Debug, PartialEq, Eq, Deserialize)]
+    struct Plugin {
+        name: String,
+        retries: Option<i64>,
+    }
+    assert_eq!(
+        parse::<Plugin>(
+            r#"json.object [
+                 { mapKey = "name", mapValue = json.string "foo" },
+                 { mapKey = "retries", mapValue = json.null }
+               ]"#
+        ),
+        Plugin {
+            name: "foo".to_owned(),
+            retries: None
+        }
+    );
+
+    // Older versions of `Prelude.JSON.Type` have a single `number` handler
+    assert_eq!(
+        from_str::<serde_json::Value>(
+            "λ(JSON : Type) → λ(json : { null : JSON, number : Double → JSON \
+             }) → json.number 2.0"
+        )
+        .unwrap(),
+        json!(2.0)
+    );
+
+    assert!(from_str::<serde_json::Value>("λ(x : Natural) → x").is_err());
 }
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use serde_dhall::{
+    from_str_auto_type, to_string, to_string_auto_type, StaticType,
+};
+
+#[test]
+fn test_ser_typed() {
+    fn print<T: Serialize + StaticType>(x: &T) -> String {
+        to_string_auto_type(x).unwrap()
+    }
+
+    assert_eq!(print(&true), "True");
+    assert_eq!(print(&1u64), "1");
+    assert_eq!(print(&-1i64), "-1");
+    assert_eq!(print(&1.5f64), "1.5");
+    assert_eq!(print(&"foo".to_owned()), r#""foo""#);
+    assert_eq!(print::<Vec<u64>>(&vec![]), "[] : List Natural");
+    assert_eq!(print(&vec![1u64, 2]), "[1, 2]");
+    assert_eq!(print::<Option<u64>>(&None), "None Natural");
+    assert_eq!(print(&Some(1u64)), "Some 1");
+    assert_eq!(
+        print(&(1u64, "foo".to_owned())),
+        r#"{ `_1` = 1, `_2` = "foo" }"#
+    );
+
+    #[derive(Debug, PartialEq, Eq, Serialize, StaticType)]
+    struct Foo {
+        x: u64,
+        y: Vec<i64>,
+    }
+    assert_eq!(
+        print(&Foo { x: 1, y: vec![] }),
+        "{ x = 1, y = [] : List Integer }"
+    );
+
+    #[derive(Debug, PartialEq, Eq, Serialize, StaticType)]
+    enum Baz {
+        X,
+        Y(i64),
+    }
+    assert_eq!(print(&Baz::X), "< X | Y: Integer >.X");
+    assert_eq!(print(&Baz::Y(1)), "< X | Y: Integer >.Y +1");
+}
+
+#[test]
+fn test_ser_untyped() {
+    fn print<T: Serialize>(x: &T) -> String {
+        to_string(x).unwrap()
+    }
+
+    assert_eq!(print(&vec![Some(1u64), None]), "[Some 1, None Natural]");
+
+    #[derive(Serialize)]
+    enum Bar {
+        X(u64),
+        Y { a: bool },
+        Z,
+    }
+    assert_eq!(print(&Bar::X(1)), "< X: Natural >.X 1");
+    assert_eq!(
+        print(&Bar::Y { a: true }),
+        "< Y: { a : Bool } >.Y { a = True }"
+    );
+    assert_eq!(print(&Bar::Z), "< Z >.Z");
+    assert_eq!(
+        print(&vec![Bar::X(1), Bar::X(2)]),
+        "[< X: Natural >.X 1, < X: Natural >.X 2]"
+    );
+    // The union type is guessed from the first element, so it only has that one variant
+    assert!(to_string(&vec![Bar::X(1), Bar::Z]).is_err());
+    let err = to_string(&vec![Bar::Z, Bar::X(1)]).unwrap_err();
+    assert!(err.to_string().contains("to_string_auto_type"), "{}", err);
+
+    // The type of empty lists and `None`s cannot be guessed
+    assert!(to_string::<Vec<u64>>(&vec![]).is_err());
+    assert!(to_string::<Option<u64>>(&None).is_err());
+    // ... nor taken from the other fields of a struct
+    #[derive(Serialize)]
+    struct Baz<T> {
+        a: T,
+        b: Vec<String>,
+        c: Option<String>,
+    }
+    assert!(to_string(&Baz {
+        a: vec![1u64],
+        b: vec![],
+        c: Some("foo".to_owned()),
+    })
+    .is_err());
+    assert!(to_string(&Baz {
+        a: 1u64,
+        b: vec!["foo".to_owned()],
+        c: None,
+    })
+    .is_err());
+    // Map values all have the same type, so the first one gives it to the others
+    let mut map = std::collections::BTreeMap::new();
+    map.insert("a", vec![1u64]);
+    map.insert("b", vec![]);
+    assert_eq!(print(&map), "{ a = [1], b = [] : List Natural }");
+    // Records must have text keys
+    let mut map = std::collections::HashMap::new();
+    map.insert(1u64, 1u64);
+    assert!(to_string(&map).is_err());
+    // ... that can be printed as labels
+    let mut map = std::collections::HashMap::new();
+    map.insert("", 1u64);
+    assert!(to_string(&map).is_err());
+    let mut map = std::collections::HashMap::new();
+    map.insert("`", 1u64);
+    assert!(to_string(&map).is_err());
+    let mut map = std::collections::HashMap::new();
+    map.insert("x y", 1u64);
+    assert_eq!(print(&map), "{ `x y` = 1 }");
+}
+
+#[test]
+fn test_ser_roundtrip() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize, StaticType)]
+    struct Config {
+        name: String,
+        port: Option<u64>,
+        tags: Vec<String>,
+        mode: Mode,
+    }
+    #[derive(Debug, PartialEq, Serialize, Deserialize, StaticType)]
+    enum Mode {
+        Fast,
+        Slow(u64),
+    }
+
+    let config = Config {
+        name: "foo \"bar\"\n".to_owned(),
+        port: None,
+        tags: vec![],
+        mode: Mode::Slow(2),
+    };
+    let text = to_string_auto_type(&config).unwrap();
+    assert_eq!(from_str_auto_type::<Config>(&text).unwrap(), config);
+}